@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE app_usages DROP COLUMN file;
+ALTER TABLE app_usages DROP COLUMN project;
@@ -0,0 +1,2 @@
+ALTER TABLE app_usages ADD COLUMN project TEXT; -- Parsed from the window title by a title rule, NULL when no rule matched
+ALTER TABLE app_usages ADD COLUMN file TEXT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW user_app_usage_totals;
+DROP TABLE sessions;
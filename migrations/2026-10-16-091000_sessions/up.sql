@@ -0,0 +1,16 @@
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY, -- Referenced by app_usages.session_id
+    session_date DATE NOT NULL,
+    user_name TEXT NOT NULL -- Windows account the tracker ran under
+);
+
+-- Per-user daily totals for machine administrators sharing one database
+CREATE VIEW user_app_usage_totals AS
+SELECT
+    sessions.user_name,
+    date(app_usages.start_time) AS usage_date,
+    app_usages.application_name,
+    SUM((julianday(app_usages.last_updated_time) - julianday(app_usages.start_time)) * 86400.0) AS seconds
+FROM app_usages
+JOIN sessions ON sessions.id = app_usages.session_id
+GROUP BY sessions.user_name, usage_date, app_usages.application_name;
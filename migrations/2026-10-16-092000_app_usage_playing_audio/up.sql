@@ -0,0 +1 @@
+ALTER TABLE app_usages ADD COLUMN playing_audio BOOLEAN NOT NULL DEFAULT 0; -- The app had an active audio session while this row was open
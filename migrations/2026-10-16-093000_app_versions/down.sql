@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE app_versions;
+ALTER TABLE apps DROP COLUMN version;
@@ -0,0 +1,10 @@
+ALTER TABLE apps ADD COLUMN version TEXT; -- Current file version of the executable
+
+CREATE TABLE app_versions (
+    application_name TEXT NOT NULL, -- Foreign key to apps.name
+    version TEXT NOT NULL,
+    first_seen DATE NOT NULL,
+    last_seen DATE NOT NULL,
+    PRIMARY KEY (application_name, version),
+    FOREIGN KEY (application_name) REFERENCES apps (name)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE sessions DROP COLUMN boot_time;
+ALTER TABLE sessions DROP COLUMN end_time;
+ALTER TABLE sessions DROP COLUMN start_time;
@@ -0,0 +1,3 @@
+ALTER TABLE sessions ADD COLUMN start_time TIMESTAMP;
+ALTER TABLE sessions ADD COLUMN end_time TIMESTAMP; -- Last time usage was written for the session
+ALTER TABLE sessions ADD COLUMN boot_time TIMESTAMP; -- Machine boot time, sessions only resume within one boot
@@ -0,0 +1,5 @@
+CREATE TABLE machine_sessions (
+    boot_time TIMESTAMP PRIMARY KEY, -- Derived from GetTickCount64 when the tracker starts
+    last_seen TIMESTAMP NOT NULL, -- Last time the tracker was running during this boot
+    shutdown_time TIMESTAMP -- Set when the tracker stopped cleanly, NULL after power loss
+);
@@ -0,0 +1 @@
+ALTER TABLE app_usages ADD COLUMN remote_session BOOLEAN NOT NULL DEFAULT 0; -- Recorded while connected over Remote Desktop
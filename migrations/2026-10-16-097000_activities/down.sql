@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_activities_session_name;
+DROP TABLE activities;
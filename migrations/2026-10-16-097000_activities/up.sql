@@ -0,0 +1,9 @@
+CREATE TABLE activities (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    name TEXT NOT NULL, -- Expanded from the matching rule in activity_rules.json
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_activities_session_name ON activities (session_id, name, end_time);
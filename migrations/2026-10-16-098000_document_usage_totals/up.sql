@@ -0,0 +1,10 @@
+-- Daily time per document for apps whose titles are parsed by title_rules.json
+CREATE VIEW document_usage_totals AS
+SELECT
+    application_name,
+    file,
+    date(start_time) AS usage_date,
+    SUM((julianday(last_updated_time) - julianday(start_time)) * 86400.0) AS seconds
+FROM app_usages
+WHERE file IS NOT NULL
+GROUP BY application_name, file, usage_date;
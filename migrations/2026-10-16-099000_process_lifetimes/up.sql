@@ -0,0 +1,8 @@
+CREATE TABLE process_lifetimes (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    process_id INTEGER NOT NULL,
+    exe_name TEXT NOT NULL, -- Matched against WATCHED_PROCESSES
+    start_time TIMESTAMP NOT NULL, -- First poll the process was seen in
+    end_time TIMESTAMP NOT NULL -- Last poll the process was seen in
+);
@@ -0,0 +1 @@
+ALTER TABLE app_usages ADD COLUMN focused BOOLEAN NOT NULL DEFAULT 1; -- The window was in the foreground for the whole row, rows from before it was tracked count as focused
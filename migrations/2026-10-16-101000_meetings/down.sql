@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_meetings_session_app;
+DROP TABLE meetings;
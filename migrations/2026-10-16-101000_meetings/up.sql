@@ -0,0 +1,9 @@
+CREATE TABLE meetings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    application_name TEXT NOT NULL, -- Conferencing app or browser the meeting ran in
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_meetings_session_app ON meetings (session_id, application_name, end_time);
@@ -0,0 +1,7 @@
+CREATE TABLE idle_thresholds (
+    application_name TEXT PRIMARY KEY,
+    typical_gap_seconds REAL NOT NULL, -- Moving average of input gaps while the app was focused
+    threshold_seconds INTEGER NOT NULL, -- Idle threshold applied while the app is focused
+    samples INTEGER NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,3 @@
+CREATE TABLE tracker_heartbeats (
+    minute TIMESTAMP PRIMARY KEY -- Start of a UTC minute in which the tracker was running
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_app_usages_app_updated;
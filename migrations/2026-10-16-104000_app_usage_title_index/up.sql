@@ -0,0 +1,2 @@
+-- Serves the most recent titles of one app
+CREATE INDEX idx_app_usages_app_updated ON app_usages (application_name, last_updated_time);
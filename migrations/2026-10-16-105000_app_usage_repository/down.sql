@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW repository_usage_totals;
+ALTER TABLE app_usages DROP COLUMN branch;
+ALTER TABLE app_usages DROP COLUMN repository;
@@ -0,0 +1,13 @@
+ALTER TABLE app_usages ADD COLUMN repository TEXT; -- Parsed from editor and terminal titles by a title rule
+ALTER TABLE app_usages ADD COLUMN branch TEXT;
+
+-- Daily coding time per repository and branch, across editors and terminals
+CREATE VIEW repository_usage_totals AS
+SELECT
+    repository,
+    branch,
+    date(start_time) AS usage_date,
+    SUM((julianday(last_updated_time) - julianday(start_time)) * 86400.0) AS seconds
+FROM app_usages
+WHERE repository IS NOT NULL
+GROUP BY repository, branch, usage_date;
@@ -0,0 +1,33 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW user_app_usage_totals;
+CREATE VIEW user_app_usage_totals AS
+SELECT
+    sessions.user_name,
+    date(app_usages.start_time) AS usage_date,
+    app_usages.application_name,
+    SUM((julianday(app_usages.last_updated_time) - julianday(app_usages.start_time)) * 86400.0) AS seconds
+FROM app_usages
+JOIN sessions ON sessions.id = app_usages.session_id
+GROUP BY sessions.user_name, usage_date, app_usages.application_name;
+
+DROP VIEW document_usage_totals;
+CREATE VIEW document_usage_totals AS
+SELECT
+    application_name,
+    file,
+    date(start_time) AS usage_date,
+    SUM((julianday(last_updated_time) - julianday(start_time)) * 86400.0) AS seconds
+FROM app_usages
+WHERE file IS NOT NULL
+GROUP BY application_name, file, usage_date;
+
+DROP VIEW repository_usage_totals;
+CREATE VIEW repository_usage_totals AS
+SELECT
+    repository,
+    branch,
+    date(start_time) AS usage_date,
+    SUM((julianday(last_updated_time) - julianday(start_time)) * 86400.0) AS seconds
+FROM app_usages
+WHERE repository IS NOT NULL
+GROUP BY repository, branch, usage_date;
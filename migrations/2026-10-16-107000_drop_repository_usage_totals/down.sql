@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+CREATE VIEW repository_usage_totals AS
+SELECT
+    repository,
+    branch,
+    date(start_time, 'localtime') AS usage_date,
+    SUM((julianday(last_updated_time) - julianday(start_time)) * 86400.0) AS seconds
+FROM app_usages
+WHERE repository IS NOT NULL
+GROUP BY repository, branch, usage_date;
@@ -0,0 +1,3 @@
+-- Summing rows double-counted editors and terminals open on the same repository,
+-- totals are now computed with merged intervals by the dashboard
+DROP VIEW repository_usage_totals;
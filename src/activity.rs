@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use log::{debug, error, info};
+use regex::Regex;
+use rusqlite::Connection;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::db::activities::{record_activity, usages_updated_since};
+use crate::db::models::IDLE_WINDOW_TITLE;
+use crate::db::reports::DayBoundary;
+use crate::disk_guard::DiskGuard;
+use crate::games::{GameLibrary, GAMES_ACTIVITY};
+
+const ACTIVITY_SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the game launchers' manifests are read again
+const GAME_RESCAN_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Matching usage rows closer together than this are joined into one activity
+const ACTIVITY_GAP_MINUTES: i64 = 5;
+
+/// A rule as written in the activity rules file
+#[derive(Debug, Deserialize)]
+struct ActivityRuleConfig {
+    activity: String,
+    app_name: Option<String>,
+    pattern: Option<String>,
+}
+
+struct ActivityRule {
+    activity: String,
+    app_name: Option<String>,
+    pattern: Option<Regex>,
+}
+
+/// Opt-in rules that map window titles to higher-level activities. A rule
+/// matches on the app name, a title regex or both, and `$name` references
+/// to the regex's named groups are expanded in the activity name.
+#[derive(Default)]
+pub struct ActivityRules {
+    rules: Vec<ActivityRule>,
+}
+
+impl ActivityRules {
+    /// Load rules from a JSON file, a missing file means summarizing is disabled
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read_rules(path) {
+            Ok(rules) => {
+                info!(
+                    "Loaded {} activity rules from {:?}",
+                    rules.rules.len(),
+                    path
+                );
+                rules
+            }
+            Err(err) => {
+                error!("Failed to load activity rules from {:?}: {:?}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    fn read_rules(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse rules in the format of the rules file
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let configs: Vec<ActivityRuleConfig> = serde_json::from_str(contents)?;
+        let rules = configs
+            .into_iter()
+            .map(|config| {
+                Ok(ActivityRule {
+                    activity: config.activity,
+                    app_name: config.app_name,
+                    pattern: config.pattern.as_deref().map(Regex::new).transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Activity names that can't carry text from window titles: those of
+    /// rules without `$` references, and the games activity
+    pub fn literal_activities(&self) -> HashSet<String> {
+        self.rules
+            .iter()
+            .filter(|rule| !rule.activity.contains('$'))
+            .map(|rule| rule.activity.clone())
+            .chain(std::iter::once(GAMES_ACTIVITY.to_string()))
+            .collect()
+    }
+
+    /// Name of the activity for the first rule matching the app and title
+    pub fn classify(&self, app_name: &str, title: &str) -> Option<String> {
+        self.rules
+            .iter()
+            .filter(|rule| {
+                rule.app_name
+                    .as_ref()
+                    .is_none_or(|name| name.eq_ignore_ascii_case(app_name))
+            })
+            .find_map(|rule| match &rule.pattern {
+                Some(pattern) => pattern.captures(title).map(|captures| {
+                    let mut name = String::new();
+                    captures.expand(&rule.activity, &mut name);
+                    name
+                }),
+                None => Some(rule.activity.clone()),
+            })
+    }
+}
+
+/// Periodically fold newly written usage rows into the activities table
+pub async fn summarize_activities(
+    conn: Arc<Mutex<Connection>>,
+    rules: ActivityRules,
+    detect_games: bool,
+    boundary: DayBoundary,
+    disk_guard: DiskGuard,
+) {
+    if rules.rules.is_empty() && !detect_games {
+        return;
+    }
+    let mut games = GameLibrary::default();
+    let mut games_scanned_at: Option<Instant> = None;
+    let gap = chrono::Duration::minutes(ACTIVITY_GAP_MINUTES);
+    // Rows are re-read while they keep growing, which only widens existing activities
+    let mut since: NaiveDateTime = boundary.start_of_today();
+    let mut interval = tokio::time::interval(ACTIVITY_SUMMARY_INTERVAL);
+    loop {
+        interval.tick().await;
+        // Rows since the last pass are picked up once space frees up
+        if disk_guard.is_low_on_space() {
+            continue;
+        }
+
+        // Pick up games installed while the tracker is running
+        if detect_games && games_scanned_at.is_none_or(|at| at.elapsed() >= GAME_RESCAN_INTERVAL) {
+            games = GameLibrary::scan();
+            games_scanned_at = Some(Instant::now());
+        }
+
+        let conn = conn.lock().await;
+        let usages = match usages_updated_since(&conn, since) {
+            Ok(usages) => usages,
+            Err(err) => {
+                error!("Failed to query usages for activities: {}", err);
+                continue;
+            }
+        };
+
+        for usage in &usages {
+            // Idle rows carry the app of the last window, which app-only rules
+            // and the games check would match
+            if usage.title == IDLE_WINDOW_TITLE {
+                continue;
+            }
+            // Rules come first so users can re-file a game under their own activity
+            let name = rules
+                .classify(&usage.application_name, &usage.title)
+                .or_else(|| {
+                    usage
+                        .app_path
+                        .as_deref()
+                        .is_some_and(|path| games.is_game(path))
+                        .then(|| GAMES_ACTIVITY.to_string())
+                });
+            let Some(name) = name else {
+                continue;
+            };
+            if let Err(err) = record_activity(&conn, &name, usage, gap) {
+                error!("Failed to record activity {:?}: {}", name, err);
+            }
+        }
+        if let Some(latest) = usages.iter().map(|usage| usage.end_time).max() {
+            since = latest;
+        }
+        debug!("Summarized {} usage rows into activities", usages.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_uses_the_first_matching_rule() {
+        let rules = ActivityRules::from_json(
+            r#"[
+                {"activity": "Code review", "app_name": "chrome.exe", "pattern": "Pull Request"},
+                {"activity": "Coding $project", "app_name": "Code.exe", "pattern": "- (?P<project>\\w+) - Visual Studio Code$"},
+                {"activity": "Browsing", "app_name": "chrome.exe"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.classify("chrome.exe", "Fix idle by someone · Pull Request #42"),
+            Some("Code review".to_string())
+        );
+        assert_eq!(
+            rules.classify("code.exe", "main.rs - tracker - Visual Studio Code"),
+            Some("Coding tracker".to_string())
+        );
+        assert_eq!(
+            rules.classify("chrome.exe", "News"),
+            Some("Browsing".to_string())
+        );
+        assert_eq!(rules.classify("notepad.exe", "Pull Request"), None);
+    }
+
+    #[test]
+    fn literal_activities_leave_out_templates() {
+        let rules = ActivityRules::from_json(
+            r#"[
+                {"activity": "Email", "app_name": "outlook.exe"},
+                {"activity": "Coding $project", "pattern": "(?P<project>\\w+)"}
+            ]"#,
+        )
+        .unwrap();
+        let mut literal: Vec<_> = rules.literal_activities().into_iter().collect();
+        literal.sort();
+        assert_eq!(literal, ["Email", GAMES_ACTIVITY]);
+    }
+}
@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, TimeZone};
+use log::{debug, error, info};
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, Semaphore};
+
+use crate::db::reports::{
+    app_totals_since, compare_apps, daily_coverage, data_gaps, meeting_totals, recent_titles,
+    repository_totals, total_screen_time, AppTotal, DataGap, DayBoundary,
+};
+use crate::network::NetworkPolicy;
+
+/// Requests larger than this are rejected, the dashboard only serves GETs
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a client gets to send its request headers
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections served at once, further ones are dropped until one finishes
+const MAX_CONNECTIONS: usize = 16;
+
+/// Failed token checks a peer gets per minute before it is answered with 429
+const MAX_AUTH_FAILURES: u32 = 5;
+
+/// Days listed by the coverage API
+const COVERAGE_DAYS: u32 = 14;
+
+/// Default and largest number of titles returned by the titles API
+const DEFAULT_TITLE_LIMIT: u32 = 20;
+const MAX_TITLE_LIMIT: u32 = 100;
+
+/// Default and largest number of days in an app comparison
+const DEFAULT_COMPARE_DAYS: u32 = 7;
+const MAX_COMPARE_DAYS: u32 = 90;
+
+/// Default and largest number of days in the repository totals
+const DEFAULT_REPOSITORY_DAYS: u32 = 7;
+const MAX_REPOSITORY_DAYS: u32 = 90;
+
+/// Default and largest number of days in the meeting totals
+const DEFAULT_MEETING_DAYS: u32 = 7;
+const MAX_MEETING_DAYS: u32 = 90;
+
+const DASHBOARD_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Screen time</title>
+<style>
+body { font-family: sans-serif; margin: 1.5em; }
+td { padding: 0.2em 1em 0.2em 0; }
+</style>
+</head>
+<body>
+<h1>Today</h1>
+<p id="total">Loading...</p>
+<p id="coverage"></p>
+<p id="gaps"></p>
+<table id="apps"></table>
+<h2>Incomplete days</h2>
+<ul id="incomplete"></ul>
+<script>
+const token = new URLSearchParams(location.search).get("token") || "";
+const format = (seconds) => `${Math.floor(seconds / 3600)}h ${Math.floor(seconds / 60) % 60}m`;
+fetch(`/api/today?token=${encodeURIComponent(token)}`)
+  .then((response) => response.ok ? response.json() : Promise.reject(response.status))
+  .then((today) => {
+    document.getElementById("total").textContent = `Total: ${format(today.total_seconds)}`;
+    document.getElementById("coverage").textContent =
+      `Tracker running ${today.coverage_percent.toFixed(0)}% of today so far`;
+    if (today.gaps.length > 0) {
+      document.getElementById("gaps").textContent =
+        `No data: ${today.gaps.map((gap) => `${gap.start}-${gap.end}`).join(", ")}`;
+    }
+    const table = document.getElementById("apps");
+    for (const app of today.apps) {
+      const row = table.insertRow();
+      row.insertCell().textContent = app.application_name;
+      row.insertCell().textContent = format(app.seconds);
+    }
+  })
+  .catch((status) => { document.getElementById("total").textContent = `Failed to load (${status})`; });
+fetch(`/api/coverage?token=${encodeURIComponent(token)}`)
+  .then((response) => response.ok ? response.json() : Promise.reject(response.status))
+  .then((days) => {
+    const list = document.getElementById("incomplete");
+    for (const day of days.filter((day) => day.coverage_percent < 99)) {
+      const item = document.createElement("li");
+      item.textContent = `${day.date}: ${day.coverage_percent.toFixed(0)}% tracked`;
+      list.appendChild(item);
+    }
+  });
+</script>
+</body>
+</html>
+"#;
+
+/// Today's stats as served by the JSON API
+#[derive(Debug, Serialize)]
+struct TodayPayload {
+    generated_at: String,
+    total_seconds: u64,
+    /// Share of today so far the tracker was running for
+    coverage_percent: f64,
+    /// Local times of today without data, which aren't zero use
+    gaps: Vec<GapPayload>,
+    apps: Vec<AppTotal>,
+}
+
+/// A period without data as local clock times
+#[derive(Debug, Serialize)]
+struct GapPayload {
+    start: String,
+    end: String,
+}
+
+impl From<DataGap> for GapPayload {
+    fn from(gap: DataGap) -> Self {
+        let format = |time| Local.from_utc_datetime(&time).format("%H:%M").to_string();
+        Self {
+            start: format(gap.start),
+            end: format(gap.end),
+        }
+    }
+}
+
+/// Serve a read-only dashboard and JSON API on `addr`. Only requests that
+/// carry `token` as a `token` query parameter or bearer token get data, and
+/// only peers on this machine or a private network are served.
+pub async fn serve_dashboard(
+    conn: Arc<Mutex<Connection>>,
+    addr: SocketAddr,
+    token: String,
+    requests_per_minute: u32,
+    unfocused_weight: f64,
+    boundary: DayBoundary,
+    network: NetworkPolicy,
+) {
+    if network.offline {
+        info!("Offline mode is on, the dashboard stays off.");
+        return;
+    }
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Failed to bind dashboard to {}: {:?}", addr, err);
+            return;
+        }
+    };
+    info!("Dashboard listening on http://{}", addr);
+
+    let token = Arc::new(token);
+    let limits = Arc::new(Limits {
+        requests: RateLimit::new(requests_per_minute),
+        auth_failures: AuthFailures::default(),
+    });
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                error!("Failed to accept dashboard connection: {:?}", err);
+                continue;
+            }
+        };
+        // Binding to 0.0.0.0 should open the dashboard to the LAN, not the internet
+        if !is_local_peer(peer.ip()) {
+            debug!("Refusing dashboard connection from non-local {}", peer);
+            continue;
+        }
+        // The connection may become metered while the dashboard is running
+        if let Some(reason) = network.blocked_reason() {
+            debug!("Refusing dashboard connection from {}: {}", peer, reason);
+            continue;
+        }
+        let Ok(permit) = connections.clone().try_acquire_owned() else {
+            debug!("Too many dashboard connections, dropping {}", peer);
+            continue;
+        };
+        let conn = conn.clone();
+        let token = token.clone();
+        let limits = limits.clone();
+        tokio::spawn(async move {
+            let handled = handle_connection(
+                stream,
+                peer.ip(),
+                &conn,
+                &token,
+                &limits,
+                unfocused_weight,
+                boundary,
+            )
+            .await;
+            if let Err(err) = handled {
+                debug!("Dashboard connection from {} failed: {:?}", peer, err);
+            }
+            drop(permit);
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    peer: IpAddr,
+    conn: &Mutex<Connection>,
+    token: &str,
+    limits: &Limits,
+    unfocused_weight: f64,
+    boundary: DayBoundary,
+) -> std::io::Result<()> {
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(request) => request?,
+        Err(_) => {
+            return write_response(&mut stream, "408 Request Timeout", "text/plain", b"").await
+        }
+    };
+    let Some(request) = request else {
+        return write_response(&mut stream, "400 Bad Request", "text/plain", b"").await;
+    };
+    let request = String::from_utf8_lossy(&request);
+
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let (method, target) = (request_line.next(), request_line.next().unwrap_or("/"));
+    if method != Some("GET") {
+        return write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"").await;
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if path.starts_with("/api/") {
+        // Checked before the token so a peer guessing tokens stops getting answers
+        if limits.auth_failures.is_blocked(peer) {
+            return write_response(&mut stream, "429 Too Many Requests", "text/plain", b"").await;
+        }
+        if !is_authorized(&request, query, token) {
+            limits.auth_failures.record(peer);
+            return write_response(&mut stream, "401 Unauthorized", "text/plain", b"").await;
+        }
+        if !limits.requests.allow() {
+            return write_response(&mut stream, "429 Too Many Requests", "text/plain", b"").await;
+        }
+    }
+
+    match path {
+        "/" => {
+            write_response(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                DASHBOARD_PAGE.as_bytes(),
+            )
+            .await
+        }
+        "/api/today" => match today_payload(conn, unfocused_weight, boundary).await {
+            Ok(body) => write_response(&mut stream, "200 OK", "application/json", &body).await,
+            Err(err) => {
+                error!("Failed to build dashboard data: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        "/api/coverage" => match coverage_payload(conn, boundary).await {
+            Ok(body) => write_response(&mut stream, "200 OK", "application/json", &body).await,
+            Err(err) => {
+                error!("Failed to build dashboard coverage: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        "/api/titles" => match titles_payload(conn, query).await {
+            Ok(Some(body)) => {
+                write_response(&mut stream, "200 OK", "application/json", &body).await
+            }
+            Ok(None) => write_response(&mut stream, "400 Bad Request", "text/plain", b"").await,
+            Err(err) => {
+                error!("Failed to build dashboard titles: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        "/api/compare" => match compare_payload(conn, query, boundary).await {
+            Ok(Some(body)) => {
+                write_response(&mut stream, "200 OK", "application/json", &body).await
+            }
+            Ok(None) => write_response(&mut stream, "400 Bad Request", "text/plain", b"").await,
+            Err(err) => {
+                error!("Failed to build dashboard comparison: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        "/api/repositories" => match repositories_payload(conn, query, boundary).await {
+            Ok(body) => write_response(&mut stream, "200 OK", "application/json", &body).await,
+            Err(err) => {
+                error!("Failed to build dashboard repository totals: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        "/api/meetings" => match meetings_payload(conn, query, boundary).await {
+            Ok(body) => write_response(&mut stream, "200 OK", "application/json", &body).await,
+            Err(err) => {
+                error!("Failed to build dashboard meeting totals: {:?}", err);
+                write_response(&mut stream, "500 Internal Server Error", "text/plain", b"").await
+            }
+        },
+        _ => write_response(&mut stream, "404 Not Found", "text/plain", b"").await,
+    }
+}
+
+/// Read up to the end of the request headers, or `None` if the client closes
+/// early or sends more than `MAX_REQUEST_BYTES`
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    Ok(Some(request))
+}
+
+/// Whether a peer is on this machine or a private network
+fn is_local_peer(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_local_peer(IpAddr::V4(ip)),
+            None => ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local(),
+        },
+    }
+}
+
+/// Limits shared by all dashboard connections
+struct Limits {
+    requests: RateLimit,
+    auth_failures: AuthFailures,
+}
+
+/// Fixed-window limit on API requests made with the token, so a leaked token
+/// can't be used to hammer the database
+struct RateLimit {
+    per_minute: u32,
+    /// Start of the current window and the requests counted in it
+    window: std::sync::Mutex<(Instant, u32)>,
+}
+
+impl RateLimit {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            window: std::sync::Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Count a request, returning whether it is within the limit
+    fn allow(&self) -> bool {
+        let mut window = self.window.lock().unwrap_or_else(|err| err.into_inner());
+        if window.0.elapsed() >= Duration::from_secs(60) {
+            *window = (Instant::now(), 0);
+        }
+        window.1 += 1;
+        window.1 <= self.per_minute
+    }
+}
+
+/// Failed token checks per peer in fixed one-minute windows
+#[derive(Default)]
+struct AuthFailures {
+    /// Start of each peer's current window and the failures counted in it
+    peers: std::sync::Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl AuthFailures {
+    /// Whether the peer has used up its failures for the current window
+    fn is_blocked(&self, peer: IpAddr) -> bool {
+        let peers = self.peers.lock().unwrap_or_else(|err| err.into_inner());
+        peers.get(&peer).is_some_and(|(started, failures)| {
+            started.elapsed() < Duration::from_secs(60) && *failures >= MAX_AUTH_FAILURES
+        })
+    }
+
+    fn record(&self, peer: IpAddr) {
+        let mut peers = self.peers.lock().unwrap_or_else(|err| err.into_inner());
+        // Drop finished windows so peers that gave up don't stay in memory
+        peers.retain(|_, (started, _)| started.elapsed() < Duration::from_secs(60));
+        peers.entry(peer).or_insert((Instant::now(), 0)).1 += 1;
+    }
+}
+
+/// Accept the token from the query string, which the page uses, or a bearer header for scripts
+fn is_authorized(request: &str, query: &str, token: &str) -> bool {
+    let from_query = url::form_urlencoded::parse(query.as_bytes())
+        .any(|(key, value)| key == "token" && constant_time_eq(value.as_bytes(), token.as_bytes()));
+    let from_header = request.lines().any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("authorization")
+                && value
+                    .trim()
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()))
+        })
+    });
+    from_query || from_header
+}
+
+/// Compare without returning at the first differing byte, so response times
+/// don't reveal how much of a guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn today_payload(
+    conn: &Mutex<Connection>,
+    unfocused_weight: f64,
+    boundary: DayBoundary,
+) -> anyhow::Result<Vec<u8>> {
+    let (total_seconds, coverage, gaps, apps) = {
+        let conn = conn.lock().await;
+        let since = boundary.start_of_today();
+        let now = Local::now().naive_utc();
+        (
+            total_screen_time(&conn, since, now)?,
+            daily_coverage(&conn, 1, boundary)?,
+            data_gaps(&conn, since, now)?,
+            app_totals_since(&conn, since, unfocused_weight)?,
+        )
+    };
+    let payload = TodayPayload {
+        generated_at: Local::now().to_rfc3339(),
+        total_seconds,
+        coverage_percent: coverage.first().map_or(0.0, |today| today.coverage_percent),
+        gaps: gaps.into_iter().map(GapPayload::from).collect(),
+        apps,
+    };
+    Ok(serde_json::to_vec(&payload)?)
+}
+
+async fn coverage_payload(
+    conn: &Mutex<Connection>,
+    boundary: DayBoundary,
+) -> anyhow::Result<Vec<u8>> {
+    let coverage = daily_coverage(&*conn.lock().await, COVERAGE_DAYS, boundary)?;
+    Ok(serde_json::to_vec(&coverage)?)
+}
+
+/// Recent titles of the `app` query parameter, for autocompleting title patterns.
+/// Returns `None` when no app is given.
+async fn titles_payload(conn: &Mutex<Connection>, query: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut app = None;
+    let mut limit = DEFAULT_TITLE_LIMIT;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "app" => app = Some(value.into_owned()),
+            "limit" => limit = value.parse().unwrap_or(DEFAULT_TITLE_LIMIT),
+            _ => {}
+        }
+    }
+    let Some(app) = app else {
+        return Ok(None);
+    };
+    let titles = recent_titles(&*conn.lock().await, &app, limit.min(MAX_TITLE_LIMIT))?;
+    Ok(Some(serde_json::to_vec(&titles)?))
+}
+
+/// Side-by-side usage of the `a` and `b` apps over `days` days.
+/// Returns `None` unless both apps are given.
+async fn compare_payload(
+    conn: &Mutex<Connection>,
+    query: &str,
+    boundary: DayBoundary,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let (mut app_a, mut app_b) = (None, None);
+    let mut days = DEFAULT_COMPARE_DAYS;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "a" => app_a = Some(value.into_owned()),
+            "b" => app_b = Some(value.into_owned()),
+            "days" => days = value.parse().unwrap_or(DEFAULT_COMPARE_DAYS),
+            _ => {}
+        }
+    }
+    let (Some(app_a), Some(app_b)) = (app_a, app_b) else {
+        return Ok(None);
+    };
+    let days = days.clamp(1, MAX_COMPARE_DAYS);
+    let comparison = compare_apps(&*conn.lock().await, &app_a, &app_b, days, boundary)?;
+    Ok(Some(serde_json::to_vec(&comparison)?))
+}
+
+/// Time per repository over the last `days` days, today included
+async fn repositories_payload(
+    conn: &Mutex<Connection>,
+    query: &str,
+    boundary: DayBoundary,
+) -> anyhow::Result<Vec<u8>> {
+    let days = url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "days")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(DEFAULT_REPOSITORY_DAYS)
+        .clamp(1, MAX_REPOSITORY_DAYS);
+    let today = boundary.today();
+    let first_day = today
+        .checked_sub_days(chrono::Days::new((days - 1).into()))
+        .unwrap_or(today);
+    let since = boundary.start_of_day(first_day);
+    let now = Local::now().naive_utc();
+    let totals = repository_totals(&*conn.lock().await, since, now, boundary)?;
+    Ok(serde_json::to_vec(&totals)?)
+}
+
+/// Meeting time per app over the last `days` days, today included
+async fn meetings_payload(
+    conn: &Mutex<Connection>,
+    query: &str,
+    boundary: DayBoundary,
+) -> anyhow::Result<Vec<u8>> {
+    let days = url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "days")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(DEFAULT_MEETING_DAYS)
+        .clamp(1, MAX_MEETING_DAYS);
+    let today = boundary.today();
+    let first_day = today
+        .checked_sub_days(chrono::Days::new((days - 1).into()))
+        .unwrap_or(today);
+    let since = boundary.start_of_day(first_day);
+    let now = Local::now().naive_utc();
+    let totals = meeting_totals(&*conn.lock().await, since, now)?;
+    Ok(serde_json::to_vec(&totals)?)
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_is_accepted_from_the_query_or_a_bearer_header() {
+        let token = "a+b&c/d";
+        let request = "GET /api/today HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert!(is_authorized(request, "days=7&token=a%2Bb%26c%2Fd", token));
+        assert!(!is_authorized(request, "token=a+b&c/d", token));
+        assert!(!is_authorized(request, "token=a%2Bb%26c", token));
+        assert!(!is_authorized(request, "", token));
+
+        let request = "GET /api/today HTTP/1.1\r\nauthorization: Bearer a+b&c/d\r\n\r\n";
+        assert!(is_authorized(request, "", token));
+        let request = "GET /api/today HTTP/1.1\r\nAuthorization: Bearer a+b&c/dd\r\n\r\n";
+        assert!(!is_authorized(request, "", token));
+    }
+
+    #[test]
+    fn only_local_peers_are_served() {
+        let local = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.1.5",
+            "169.254.0.7",
+            "::1",
+            "fd12::1",
+            "fe80::1",
+            "::ffff:192.168.1.5",
+        ];
+        let remote = ["8.8.8.8", "2001:db8::1", "::ffff:8.8.8.8"];
+        for ip in local {
+            assert!(is_local_peer(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in remote {
+            assert!(!is_local_peer(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn peers_are_blocked_after_repeated_failures() {
+        let failures = AuthFailures::default();
+        let peer: IpAddr = "192.168.1.5".parse().unwrap();
+        for _ in 0..MAX_AUTH_FAILURES {
+            assert!(!failures.is_blocked(peer));
+            failures.record(peer);
+        }
+        assert!(failures.is_blocked(peer));
+        assert!(!failures.is_blocked("192.168.1.6".parse().unwrap()));
+    }
+}
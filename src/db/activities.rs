@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+const USAGES_UPDATED_SINCE_QUERY: &str = r#"
+    SELECT
+        app_usages.session_id,
+        app_usages.application_name,
+        app_usages.current_screen_title,
+        app_usages.start_time,
+        app_usages.last_updated_time,
+        app_usages.playing_audio,
+        apps.path AS app_path
+    FROM app_usages
+    LEFT JOIN apps ON apps.name = app_usages.application_name
+    WHERE app_usages.last_updated_time > ?1
+    ORDER BY app_usages.start_time
+"#;
+
+const ACTIVITY_EXTEND_QUERY: &str = r#"
+    UPDATE activities SET
+        start_time = min(start_time, ?4),
+        end_time = max(end_time, ?5)
+    WHERE id = (
+        SELECT id FROM activities
+        WHERE session_id = ?1 AND name = ?2 AND end_time >= ?3 AND start_time <= ?5
+        ORDER BY end_time DESC
+        LIMIT 1
+    )
+"#;
+
+const ACTIVITY_INSERT_QUERY: &str = r#"
+    INSERT INTO activities (session_id, name, start_time, end_time)
+    VALUES (?1, ?2, ?3, ?4)
+"#;
+
+/// A usage row as seen by the activity summarizer
+#[derive(Debug, Clone)]
+pub struct UsageSpan {
+    pub session_id: String,
+    pub application_name: String,
+    /// Executable path of the app, if it was recorded
+    pub app_path: Option<String>,
+    pub title: String,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub playing_audio: bool,
+}
+
+/// Usage rows that were written after `since`, oldest first
+pub fn usages_updated_since(
+    conn: &Connection,
+    since: NaiveDateTime,
+) -> SqliteResult<Vec<UsageSpan>> {
+    let mut stmt = conn.prepare(USAGES_UPDATED_SINCE_QUERY)?;
+    let rows = stmt.query_map(params![since], |row| {
+        Ok(UsageSpan {
+            session_id: row.get(0)?,
+            application_name: row.get(1)?,
+            title: row.get(2)?,
+            start_time: row.get(3)?,
+            end_time: row.get(4)?,
+            playing_audio: row.get(5)?,
+            app_path: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Extend the activity that ended less than `gap` before `usage` started, or start a new one
+pub fn record_activity(
+    conn: &Connection,
+    name: &str,
+    usage: &UsageSpan,
+    gap: chrono::Duration,
+) -> SqliteResult<()> {
+    let extended = conn.execute(
+        ACTIVITY_EXTEND_QUERY,
+        params![
+            usage.session_id,
+            name,
+            usage.start_time - gap,
+            usage.start_time,
+            usage.end_time
+        ],
+    )?;
+    if extended == 0 {
+        conn.execute(
+            ACTIVITY_INSERT_QUERY,
+            params![usage.session_id, name, usage.start_time, usage.end_time],
+        )?;
+    }
+    Ok(())
+}
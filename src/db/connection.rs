@@ -1,16 +1,60 @@
-use log::{debug, error};
-use rusqlite::{params, Connection, Result as SqliteResult};
-use std::{collections::HashMap, sync::Arc};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use log::{debug, error, info};
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Result as SqliteResult};
+use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::Instant;
 
-use super::models::{App, AppUsage};
+use super::models::{App, AppUsage, Sessions};
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+use crate::retry::RetryPolicy;
+
+type AppData = (HashMap<String, App>, HashMap<String, AppUsage>);
+
+/// Number of snapshots the tracker may queue before it starts coalescing locally
+pub const UPDATE_CHANNEL_CAPACITY: usize = 8;
+
+/// Snapshots received within this window of the first one are written together
+const BATCH_WINDOW: Duration = Duration::from_millis(500);
+
+/// Backoff between failed writes; the connection is reopened once the attempts run out
+const WRITE_RETRY: RetryPolicy = RetryPolicy {
+    initial_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(30),
+    max_attempts: 3,
+};
+
+/// Backoff for reopening the database file
+const REOPEN_RETRY: RetryPolicy = RetryPolicy {
+    initial_delay: Duration::from_millis(200),
+    max_delay: Duration::from_secs(5),
+    max_attempts: 5,
+};
+
+/// Consecutive failed writes after which the user is told data isn't being saved
+const ALERT_AFTER_FAILURES: u32 = 5;
+
+/// Rows kept in memory while writes fail, beyond this the oldest closed usages are dropped
+const MAX_UNWRITTEN_ROWS: usize = 10_000;
 
 const APP_UPSERT_QUERY: &str = r#"
-    INSERT INTO apps (name, path) 
-    VALUES (?1, ?2)
-    ON CONFLICT(name) DO UPDATE SET 
-        path = excluded.path
+    INSERT INTO apps (name, path, version)
+    VALUES (?1, ?2, ?3)
+    ON CONFLICT(name) DO UPDATE SET
+        path = excluded.path,
+        version = excluded.version
+"#;
+
+const APP_VERSION_UPSERT_QUERY: &str = r#"
+    INSERT INTO app_versions (application_name, version, first_seen, last_seen)
+    VALUES (?1, ?2, ?3, ?3)
+    ON CONFLICT(application_name, version) DO UPDATE SET
+        last_seen = excluded.last_seen
 "#;
 
 const USAGE_UPSERT_QUERY: &str = r#"
@@ -20,12 +64,64 @@ const USAGE_UPSERT_QUERY: &str = r#"
         application_name, 
         current_screen_title, 
         start_time,
-        last_updated_time
-    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        last_updated_time,
+        project,
+        file,
+        playing_audio,
+        remote_session,
+        focused,
+        repository,
+        branch
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
     ON CONFLICT(id) DO UPDATE SET
-        last_updated_time = excluded.last_updated_time
+        last_updated_time = excluded.last_updated_time,
+        playing_audio = excluded.playing_audio
+"#;
+
+const SESSION_INSERT_QUERY: &str = r#"
+    INSERT INTO sessions (id, session_date, user_name, start_time, end_time, boot_time)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+"#;
+
+const RESUMABLE_SESSION_QUERY: &str = r#"
+    SELECT id FROM sessions
+    WHERE user_name = ?1
+        AND end_time >= ?2
+        AND abs(julianday(boot_time) - julianday(?3)) * 86400.0 <= ?4
+    ORDER BY end_time DESC
+    LIMIT 1
+"#;
+
+const SESSION_END_UPDATE_QUERY: &str = r#"
+    UPDATE sessions SET end_time = ?2
+    WHERE id = ?1 AND (end_time IS NULL OR end_time < ?2)
+"#;
+
+const MACHINE_BOOT_QUERY: &str = r#"
+    SELECT boot_time FROM machine_sessions
+    WHERE abs(julianday(boot_time) - julianday(?1)) * 86400.0 <= ?2
+    ORDER BY boot_time DESC
+    LIMIT 1
 "#;
 
+const MACHINE_SESSION_INSERT_QUERY: &str = r#"
+    INSERT INTO machine_sessions (boot_time, last_seen)
+    VALUES (?1, ?2)
+"#;
+
+const MACHINE_LAST_SEEN_UPDATE_QUERY: &str = r#"
+    UPDATE machine_sessions SET last_seen = ?2
+    WHERE boot_time = ?1
+"#;
+
+const MACHINE_SHUTDOWN_UPDATE_QUERY: &str = r#"
+    UPDATE machine_sessions SET last_seen = ?2, shutdown_time = ?2
+    WHERE boot_time = ?1
+"#;
+
+/// Boot times computed from the tick count drift slightly between runs
+const BOOT_TIME_TOLERANCE_SECS: f64 = 60.0;
+
 /// Database operations handler
 struct DbHandler {
     conn: Arc<Mutex<Connection>>,
@@ -36,60 +132,196 @@ impl DbHandler {
         Self { conn }
     }
 
-    /// Update app information in the database
-    async fn update_apps(&self, apps: &HashMap<String, App>) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
+    /// Replace the shared connection with a fresh one to the same file
+    async fn reopen(&self) -> SqliteResult<()> {
+        let mut conn = self.conn.lock().await;
+        let path = match conn.path() {
+            Some(path) if !path.is_empty() => path.to_string(),
+            _ => return Ok(()),
+        };
+        *conn = Connection::open(&path)?;
+        info!("Reopened database connection at {}", path);
+        Ok(())
+    }
 
+    /// Write a batch in one transaction. Each row gets its own savepoint so a
+    /// malformed row is rolled back and dead-lettered without losing the rest.
+    /// Returns the number of dead-lettered rows.
+    async fn write_batch(
+        &self,
+        apps: &HashMap<String, App>,
+        app_usages: &HashMap<String, AppUsage>,
+    ) -> SqliteResult<usize> {
+        let mut conn = self.conn.lock().await;
+        let dead_letter_path = conn
+            .path()
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(format!("{}-dead-letter.log", path)));
+        let today = Local::now().date_naive();
+        let mut tx = conn.transaction()?;
+        let mut dead_lettered = 0;
+
+        // Update apps first as they are referenced by usages
         for (app_id, app) in apps {
-            match conn.execute(APP_UPSERT_QUERY, params![app.name, app.path]) {
-                Ok(_) => debug!("Successfully updated app: {}", app_id),
+            let savepoint = tx.savepoint()?;
+            match write_app(&savepoint, app, today) {
+                Ok(_) => {
+                    savepoint.commit()?;
+                    debug!("Successfully updated app: {}", app_id);
+                }
+                Err(err) if is_connection_error(&err) => return Err(err),
                 Err(err) => {
                     error!("Error updating app '{}': {}", app_id, err);
-                    return Err(err);
+                    dead_letter(dead_letter_path.as_deref(), "app", app, &err);
+                    dead_lettered += 1;
                 }
             }
         }
-        Ok(())
-    }
-
-    /// Update app usage information in the database
-    async fn update_app_usages(&self, app_usages: &HashMap<String, AppUsage>) -> SqliteResult<()> {
-        let conn = self.conn.lock().await;
 
         for (usage_id, usage) in app_usages {
-            match conn.execute(
-                USAGE_UPSERT_QUERY,
-                params![
-                    usage.app_id,
-                    usage.session_id,
-                    usage.application_name,
-                    usage.current_screen_title,
-                    usage.start_time,
-                    usage.last_updated_time,
-                ],
-            ) {
-                Ok(_) => debug!("Successfully updated usage: {}", usage_id),
+            let savepoint = tx.savepoint()?;
+            match write_app_usage(&savepoint, usage) {
+                Ok(_) => {
+                    savepoint.commit()?;
+                    debug!("Successfully updated usage: {}", usage_id);
+                }
+                Err(err) if is_connection_error(&err) => return Err(err),
                 Err(err) => {
                     error!("Error updating app usage '{}': {}", usage_id, err);
-                    return Err(err);
+                    dead_letter(dead_letter_path.as_deref(), "app usage", usage, &err);
+                    dead_lettered += 1;
                 }
             }
         }
-        Ok(())
+
+        tx.commit()?;
+        Ok(dead_lettered)
+    }
+}
+
+/// Upsert an app and the version it was seen with
+fn write_app(conn: &Connection, app: &App, today: NaiveDate) -> SqliteResult<()> {
+    conn.execute(APP_UPSERT_QUERY, params![app.name, app.path, app.version])?;
+    if let Some(version) = &app.version {
+        conn.execute(APP_VERSION_UPSERT_QUERY, params![app.name, version, today])?;
     }
+    Ok(())
+}
+
+/// Upsert a usage row and extend its session
+fn write_app_usage(conn: &Connection, usage: &AppUsage) -> SqliteResult<()> {
+    conn.execute(
+        USAGE_UPSERT_QUERY,
+        params![
+            usage.app_id,
+            usage.session_id,
+            usage.application_name,
+            usage.current_screen_title,
+            usage.start_time,
+            usage.last_updated_time,
+            usage.project,
+            usage.file,
+            usage.playing_audio,
+            usage.remote_session,
+            usage.focused,
+            usage.repository,
+            usage.branch,
+        ],
+    )?;
+    conn.execute(
+        SESSION_END_UPDATE_QUERY,
+        params![usage.session_id, usage.last_updated_time],
+    )?;
+    Ok(())
+}
+
+/// Errors caused by the database file rather than the row, which fail the whole
+/// batch so it is kept and retried
+fn is_connection_error(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(
+            ErrorCode::DatabaseBusy
+                | ErrorCode::DatabaseLocked
+                | ErrorCode::CannotOpen
+                | ErrorCode::DiskFull
+                | ErrorCode::SystemIoFailure
+                | ErrorCode::ReadOnly
+                | ErrorCode::OutOfMemory
+                | ErrorCode::NotADatabase
+                | ErrorCode::DatabaseCorrupt
+        )
+    )
+}
+
+/// Append a row that could not be written to the dead-letter log next to the database
+fn dead_letter(path: Option<&Path>, kind: &str, row: &dyn Debug, err: &rusqlite::Error) {
+    let Some(path) = path else {
+        return;
+    };
+    let line = format!(
+        "{} [{}] {} - {:?}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        kind,
+        err,
+        row
+    );
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(err) = written {
+        error!("Failed to write dead-letter log {:?}: {:?}", path, err);
+    }
+}
+
+/// Tell the user tracked data isn't being saved and what is likely to fix it
+async fn alert_write_failures(db_handler: &DbHandler, err: &rusqlite::Error) {
+    let path = db_handler
+        .conn
+        .lock()
+        .await
+        .path()
+        .unwrap_or_default()
+        .to_string();
+    let remediation = match err.sqlite_error_code() {
+        Some(ErrorCode::DiskFull) => format!("Free up space on the drive holding {}.", path),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => format!(
+            "Close other programs that have {} open, such as database browsers.",
+            path
+        ),
+        Some(ErrorCode::ReadOnly | ErrorCode::CannotOpen | ErrorCode::PermissionDenied) => {
+            format!("Check that {} exists and is writable.", path)
+        }
+        _ => format!("Check the application log next to {} for details.", path),
+    };
+    let message = format!(
+        "Screen time could not be saved {} times in a row ({}). New data is kept in memory for now and will be lost if the tracker exits.\n\n{}",
+        ALERT_AFTER_FAILURES, err, remediation
+    );
+    error!("{}", message);
+    WindowsHandle::show_alert("Screen time tracker", &message);
 }
 
 /// Metrics for database operations
 #[derive(Debug)]
 struct DbMetrics {
+    snapshots_count: usize,
     apps_count: usize,
     usages_count: usize,
     duration: std::time::Duration,
 }
 
 impl DbMetrics {
-    fn new(apps_count: usize, usages_count: usize, duration: std::time::Duration) -> Self {
+    fn new(
+        snapshots_count: usize,
+        apps_count: usize,
+        usages_count: usize,
+        duration: std::time::Duration,
+    ) -> Self {
         Self {
+            snapshots_count,
             apps_count,
             usages_count,
             duration,
@@ -98,44 +330,397 @@ impl DbMetrics {
 
     fn log(&self) {
         debug!(
-            "DB Update Metrics - Apps: {}, Usages: {}, Duration: {:?}",
-            self.apps_count, self.usages_count, self.duration
+            "DB Update Metrics - Snapshots: {}, Apps: {}, Usages: {}, Duration: {:?}",
+            self.snapshots_count, self.apps_count, self.usages_count, self.duration
         );
     }
 }
 
-/// Process database updates for apps and their usage
-pub async fn upset_app_usage(
-    conn: Arc<Mutex<Connection>>,
-    mut rx: mpsc::UnboundedReceiver<(HashMap<String, App>, HashMap<String, AppUsage>)>,
-) {
-    let db_handler = DbHandler::new(conn);
+/// Record a new tracking session and the user it belongs to
+pub fn create_session(conn: &Connection, session: &Sessions) -> SqliteResult<()> {
+    conn.execute(
+        SESSION_INSERT_QUERY,
+        params![
+            session.id,
+            session.session_date,
+            session.user_name,
+            session.start_time,
+            session.end_time,
+            session.boot_time,
+        ],
+    )?;
+    Ok(())
+}
 
-    while let Some((apps, app_usages)) = rx.recv().await {
+/// Find the user's session from the same boot that ended at or after `ended_after`
+pub fn find_resumable_session(
+    conn: &Connection,
+    user_name: &str,
+    boot_time: NaiveDateTime,
+    ended_after: NaiveDateTime,
+) -> SqliteResult<Option<String>> {
+    conn.query_row(
+        RESUMABLE_SESSION_QUERY,
+        params![user_name, ended_after, boot_time, BOOT_TIME_TOLERANCE_SECS],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Record the current boot, returning the stored boot time when this boot is already known
+pub fn record_machine_boot(
+    conn: &Connection,
+    boot_time: NaiveDateTime,
+    now: NaiveDateTime,
+) -> SqliteResult<NaiveDateTime> {
+    let known: Option<NaiveDateTime> = conn
+        .query_row(
+            MACHINE_BOOT_QUERY,
+            params![boot_time, BOOT_TIME_TOLERANCE_SECS],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match known {
+        Some(known) => {
+            conn.execute(MACHINE_LAST_SEEN_UPDATE_QUERY, params![known, now])?;
+            Ok(known)
+        }
+        None => {
+            conn.execute(MACHINE_SESSION_INSERT_QUERY, params![boot_time, now])?;
+            Ok(boot_time)
+        }
+    }
+}
+
+/// Mark the machine as still powered on
+pub fn update_machine_last_seen(
+    conn: &Connection,
+    boot_time: NaiveDateTime,
+    now: NaiveDateTime,
+) -> SqliteResult<()> {
+    conn.execute(MACHINE_LAST_SEEN_UPDATE_QUERY, params![boot_time, now])?;
+    Ok(())
+}
+
+/// Record a clean shutdown of the tracker for the current boot
+pub fn record_machine_shutdown(
+    conn: &Connection,
+    boot_time: NaiveDateTime,
+    now: NaiveDateTime,
+) -> SqliteResult<()> {
+    conn.execute(MACHINE_SHUTDOWN_UPDATE_QUERY, params![boot_time, now])?;
+    Ok(())
+}
+
+/// Merge a newer snapshot into an older one, newer rows win on key conflicts
+pub fn merge_app_data(into: &mut AppData, newer: AppData) {
+    let (apps, app_usages) = newer;
+    into.0.extend(apps);
+    into.1.extend(app_usages);
+}
+
+/// Drop the oldest closed usages until `data` fits in `max_rows`. Apps and the
+/// usages updated by the latest snapshot, which are still open, are always kept.
+/// Returns the number of dropped usages.
+fn trim_unwritten(data: &mut AppData, max_rows: usize) -> usize {
+    let (apps, app_usages) = data;
+    let excess = (apps.len() + app_usages.len()).saturating_sub(max_rows);
+    if excess == 0 {
+        return 0;
+    }
+    let Some(latest) = app_usages
+        .values()
+        .map(|usage| usage.last_updated_time)
+        .max()
+    else {
+        return 0;
+    };
+    let mut closed: Vec<_> = app_usages
+        .iter()
+        .filter(|(_, usage)| usage.last_updated_time < latest)
+        .map(|(key, usage)| (usage.last_updated_time, key.clone()))
+        .collect();
+    closed.sort();
+    closed.truncate(excess);
+    for (_, key) in &closed {
+        app_usages.remove(key);
+    }
+    closed.len()
+}
+
+/// Writes batches, keeping the rows of failed writes for the next attempt and
+/// backing off or reopening the file while failures persist
+struct BatchWriter {
+    db_handler: DbHandler,
+    // Rows from failed writes, retried with the next batch
+    unwritten: Option<AppData>,
+    consecutive_failures: u32,
+    // Unlike consecutive_failures this isn't reset by reopening the connection
+    failed_writes: u32,
+    // The user was told about the current outage, which lasts until a write succeeds
+    alerted: bool,
+}
+
+impl BatchWriter {
+    fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self {
+            db_handler: DbHandler::new(conn),
+            unwritten: None,
+            consecutive_failures: 0,
+            failed_writes: 0,
+            alerted: false,
+        }
+    }
+
+    /// Write `batch` together with the rows left over from failed writes
+    async fn write(&mut self, mut batch: AppData, snapshots_count: usize) {
+        if let Some(mut older) = self.unwritten.take() {
+            merge_app_data(&mut older, batch);
+            batch = older;
+        }
+
+        let (apps, app_usages) = &batch;
         let start = Instant::now();
 
         // Process updates
-        let result = process_updates(&db_handler, &apps, &app_usages).await;
+        let result = self.db_handler.write_batch(apps, app_usages).await;
 
         // Log metrics
-        let metrics = DbMetrics::new(apps.len(), app_usages.len(), start.elapsed());
+        let metrics = DbMetrics::new(
+            snapshots_count,
+            apps.len(),
+            app_usages.len(),
+            start.elapsed(),
+        );
         metrics.log();
 
-        // Handle any errors
-        if let Err(err) = result {
-            error!("Failed to process database updates: {}", err);
+        match result {
+            Ok(dead_lettered) => {
+                self.consecutive_failures = 0;
+                if self.alerted {
+                    info!(
+                        "Database writes recovered after {} failures.",
+                        self.failed_writes
+                    );
+                }
+                self.failed_writes = 0;
+                self.alerted = false;
+                if dead_lettered > 0 {
+                    error!("{} rows were moved to the dead-letter log.", dead_lettered);
+                }
+            }
+            Err(err) => {
+                error!("Failed to process database updates: {}", err);
+                self.consecutive_failures += 1;
+                self.failed_writes += 1;
+                if self.failed_writes >= ALERT_AFTER_FAILURES && !self.alerted {
+                    alert_write_failures(&self.db_handler, &err).await;
+                    self.alerted = true;
+                }
+
+                let dropped = trim_unwritten(&mut batch, MAX_UNWRITTEN_ROWS);
+                if dropped > 0 {
+                    error!(
+                        "Dropped the {} oldest unwritten usages over the buffer limit.",
+                        dropped
+                    );
+                }
+                self.unwritten = Some(batch);
+
+                if WRITE_RETRY.is_exhausted(self.consecutive_failures) {
+                    let reopened = REOPEN_RETRY
+                        .retry("Reopening database", || self.db_handler.reopen())
+                        .await;
+                    if let Err(err) = reopened {
+                        error!("Failed to reopen database connection: {}", err);
+                    }
+                    self.consecutive_failures = 0;
+                } else {
+                    // Back off before the next attempt; snapshots queue up in the meantime
+                    tokio::time::sleep(WRITE_RETRY.delay(self.consecutive_failures)).await;
+                }
+            }
         }
     }
 }
 
-/// Process both app and usage updates in a single transaction
-async fn process_updates(
-    db_handler: &DbHandler,
-    apps: &HashMap<String, App>,
-    app_usages: &HashMap<String, AppUsage>,
-) -> SqliteResult<()> {
-    // Update apps first as they are referenced by usages
-    db_handler.update_apps(apps).await?;
-    db_handler.update_app_usages(app_usages).await?;
-    Ok(())
+/// Process database updates for apps and their usage
+pub async fn upset_app_usage(conn: Arc<Mutex<Connection>>, mut rx: mpsc::Receiver<AppData>) {
+    let mut writer = BatchWriter::new(conn);
+
+    while let Some(mut batch) = rx.recv().await {
+        // Coalesce everything that arrives shortly after the first snapshot into one write
+        let deadline = Instant::now() + BATCH_WINDOW;
+        let mut snapshots_count = 1;
+        while let Ok(Some(next)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            merge_app_data(&mut batch, next);
+            snapshots_count += 1;
+        }
+        writer.write(batch, snapshots_count).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{test_connection, test_connection_at};
+
+    fn usage(title: &str) -> AppUsage {
+        AppUsage {
+            current_screen_title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A minute-long code.exe usage that was last updated at 09:`minute`
+    fn usage_until(id: &str, minute: u32) -> AppUsage {
+        let end = NaiveDate::from_ymd_opt(2026, 3, 10)
+            .and_then(|date| date.and_hms_opt(9, minute, 0))
+            .unwrap();
+        AppUsage {
+            app_id: id.to_string(),
+            session_id: "session".to_string(),
+            application_name: "code.exe".to_string(),
+            current_screen_title: id.to_string(),
+            start_time: end - chrono::Duration::minutes(1),
+            last_updated_time: end,
+            ..Default::default()
+        }
+    }
+
+    fn batch(usages: &[AppUsage]) -> AppData {
+        let app = App {
+            name: "code.exe".to_string(),
+            path: "code.exe".to_string(),
+            version: None,
+        };
+        let usages = usages
+            .iter()
+            .map(|usage| (usage.app_id.clone(), usage.clone()))
+            .collect();
+        (HashMap::from([(app.name.clone(), app)]), usages)
+    }
+
+    fn usage_ids(data: &AppData) -> Vec<&str> {
+        let mut ids: Vec<_> = data.1.keys().map(String::as_str).collect();
+        ids.sort();
+        ids
+    }
+
+    fn stored_usage_ids(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT id FROM app_usages ORDER BY id")
+            .unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+        rows.collect::<SqliteResult<_>>().unwrap()
+    }
+
+    #[test]
+    fn merge_app_data_prefers_newer_rows() {
+        let app = |path: &str| App {
+            name: "code.exe".to_string(),
+            path: path.to_string(),
+            version: None,
+        };
+        let mut older: AppData = (
+            HashMap::from([("code.exe".to_string(), app("old"))]),
+            HashMap::from([
+                ("a".to_string(), usage("old title")),
+                ("b".to_string(), usage("kept")),
+            ]),
+        );
+        let newer: AppData = (
+            HashMap::from([("code.exe".to_string(), app("new"))]),
+            HashMap::from([
+                ("a".to_string(), usage("new title")),
+                ("c".to_string(), usage("added")),
+            ]),
+        );
+        merge_app_data(&mut older, newer);
+
+        let (apps, usages) = older;
+        assert_eq!(apps["code.exe"].path, "new");
+        let mut titles: Vec<_> = usages
+            .iter()
+            .map(|(key, usage)| (key.as_str(), usage.current_screen_title.as_str()))
+            .collect();
+        titles.sort();
+        assert_eq!(titles, [("a", "new title"), ("b", "kept"), ("c", "added")]);
+    }
+
+    #[test]
+    fn trimming_drops_the_oldest_closed_usages() {
+        let mut data = batch(&[
+            usage_until("a", 1),
+            usage_until("b", 2),
+            usage_until("c", 3),
+            usage_until("d", 5),
+            usage_until("e", 5),
+        ]);
+        assert_eq!(trim_unwritten(&mut data, 4), 2);
+        assert_eq!(usage_ids(&data), ["c", "d", "e"]);
+        assert_eq!(data.0.len(), 1);
+
+        // Apps and open usages are kept even when they alone are over the cap
+        assert_eq!(trim_unwritten(&mut data, 1), 1);
+        assert_eq!(usage_ids(&data), ["d", "e"]);
+        assert_eq!(data.0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_writes_are_retried_with_the_next_batch() {
+        let conn = Arc::new(Mutex::new(test_connection()));
+        let mut writer = BatchWriter::new(conn.clone());
+
+        let read_only = |enabled: bool| {
+            let conn = conn.clone();
+            async move {
+                conn.lock()
+                    .await
+                    .pragma_update(None, "query_only", enabled)
+                    .unwrap();
+            }
+        };
+        read_only(true).await;
+        writer.write(batch(&[usage_until("a", 1)]), 1).await;
+        assert_eq!(writer.unwritten.as_ref().map(usage_ids), Some(vec!["a"]));
+
+        read_only(false).await;
+        writer.write(batch(&[usage_until("b", 2)]), 1).await;
+        assert!(writer.unwritten.is_none());
+        assert_eq!(stored_usage_ids(&*conn.lock().await), ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn rows_that_fail_on_their_own_are_dead_lettered() {
+        let dir = std::env::temp_dir().join(format!("tracker-dead-letter-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let conn = test_connection_at(&dir.join("tracker.db"));
+        let log_path = format!("{}-dead-letter.log", conn.path().unwrap());
+        let handler = DbHandler::new(Arc::new(Mutex::new(conn)));
+
+        // The usage's app isn't in the batch or the database
+        let mut orphan = usage_until("orphan", 2);
+        orphan.application_name = "missing.exe".to_string();
+        let (apps, usages) = batch(&[usage_until("a", 1), orphan]);
+        assert_eq!(handler.write_batch(&apps, &usages).await.unwrap(), 1);
+
+        assert_eq!(stored_usage_ids(&*handler.conn.lock().await), ["a"]);
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(log.contains("[app usage]") && log.contains("\"orphan\""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn connection_errors_fail_the_whole_batch() {
+        let conn = test_connection();
+        conn.pragma_update(None, "query_only", true).unwrap();
+        let handler = DbHandler::new(Arc::new(Mutex::new(conn)));
+
+        let (apps, usages) = batch(&[usage_until("a", 1), usage_until("b", 2)]);
+        let err = handler.write_batch(&apps, &usages).await.unwrap_err();
+        assert!(is_connection_error(&err));
+    }
 }
@@ -0,0 +1,17 @@
+use chrono::{NaiveDateTime, Timelike};
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+const HEARTBEAT_INSERT_QUERY: &str = r#"
+    INSERT OR IGNORE INTO tracker_heartbeats (minute)
+    VALUES (?1)
+"#;
+
+/// Mark the minute containing `now` as covered by the tracker
+pub fn record_heartbeat(conn: &Connection, now: NaiveDateTime) -> SqliteResult<()> {
+    let minute = now
+        .with_second(0)
+        .and_then(|time| time.with_nanosecond(0))
+        .unwrap_or(now);
+    conn.execute(HEARTBEAT_INSERT_QUERY, params![minute])?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+use super::models::LearnedIdleThreshold;
+
+const IDLE_THRESHOLDS_QUERY: &str = r#"
+    SELECT application_name, typical_gap_seconds, samples
+    FROM idle_thresholds
+"#;
+
+const IDLE_THRESHOLD_UPSERT_QUERY: &str = r#"
+    INSERT INTO idle_thresholds (application_name, typical_gap_seconds, threshold_seconds, samples, updated_at)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT(application_name) DO UPDATE SET
+        typical_gap_seconds = excluded.typical_gap_seconds,
+        threshold_seconds = excluded.threshold_seconds,
+        samples = excluded.samples,
+        updated_at = excluded.updated_at
+"#;
+
+/// Learned input gaps keyed by application name
+pub fn load_idle_thresholds(
+    conn: &Connection,
+) -> SqliteResult<HashMap<String, LearnedIdleThreshold>> {
+    let mut stmt = conn.prepare(IDLE_THRESHOLDS_QUERY)?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            LearnedIdleThreshold {
+                typical_gap_seconds: row.get(1)?,
+                samples: row.get(2)?,
+            },
+        ))
+    })?;
+    rows.collect()
+}
+
+/// Store an app's learned gap together with the threshold it currently results in
+pub fn save_idle_threshold(
+    conn: &Connection,
+    application_name: &str,
+    learned: &LearnedIdleThreshold,
+    threshold_seconds: u64,
+    now: NaiveDateTime,
+) -> SqliteResult<()> {
+    conn.execute(
+        IDLE_THRESHOLD_UPSERT_QUERY,
+        params![
+            application_name,
+            learned.typical_gap_seconds,
+            threshold_seconds,
+            learned.samples,
+            now,
+        ],
+    )?;
+    Ok(())
+}
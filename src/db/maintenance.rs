@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, Timelike};
+use log::{error, info};
+use rusqlite::{Connection, Result as SqliteResult};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::disk_guard::DiskGuard;
+
+/// How often the scheduler wakes up to check whether maintenance is due
+const MAINTENANCE_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// `PRAGMA auto_vacuum` value for incremental mode
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+const USER_TABLES_QUERY: &str = r#"
+    SELECT name FROM sqlite_master
+    WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+    ORDER BY name
+"#;
+
+const OLDEST_USAGE_QUERY: &str = "SELECT MIN(start_time) FROM app_usages";
+
+/// Size of the database on disk and what it holds
+#[derive(Debug, Serialize)]
+pub struct StorageStats {
+    pub db_size_bytes: u64,
+    pub wal_size_bytes: u64,
+    pub table_row_counts: Vec<(String, u64)>,
+    pub oldest_record: Option<NaiveDateTime>,
+}
+
+/// Collect file sizes, per-table row counts and the oldest usage record
+pub fn get_storage_stats(conn: &Connection) -> SqliteResult<StorageStats> {
+    let file_size = |path: &str| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (db_size_bytes, wal_size_bytes) = match conn.path() {
+        Some(path) if !path.is_empty() => (file_size(path), file_size(&format!("{}-wal", path))),
+        _ => (0, 0),
+    };
+
+    let tables = conn
+        .prepare(USER_TABLES_QUERY)?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    let table_row_counts = tables
+        .into_iter()
+        .map(|table| {
+            let query = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+            let count: i64 = conn.query_row(&query, [], |row| row.get(0))?;
+            Ok((table, count as u64))
+        })
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let oldest_record = conn.query_row(OLDEST_USAGE_QUERY, [], |row| row.get(0))?;
+
+    Ok(StorageStats {
+        db_size_bytes,
+        wal_size_bytes,
+        table_row_counts,
+        oldest_record,
+    })
+}
+
+/// Run optimize, analyze and vacuum, logging each step
+pub fn run_maintenance(conn: &Connection) -> SqliteResult<()> {
+    let start = Instant::now();
+    info!("Database maintenance started.");
+    match get_storage_stats(conn) {
+        Ok(stats) => info!("Storage before maintenance: {:?}", stats),
+        Err(err) => error!("Failed to collect storage stats: {}", err),
+    }
+
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    info!("WAL checkpoint finished after {:?}", start.elapsed());
+
+    conn.execute_batch("PRAGMA optimize;")?;
+    info!("PRAGMA optimize finished after {:?}", start.elapsed());
+
+    conn.execute_batch("ANALYZE;")?;
+    info!("ANALYZE finished after {:?}", start.elapsed());
+
+    let auto_vacuum: i64 = conn.query_row("PRAGMA auto_vacuum;", [], |row| row.get(0))?;
+    if auto_vacuum == AUTO_VACUUM_INCREMENTAL {
+        // Each step frees a page, so drain the pragma instead of stepping once
+        let mut stmt = conn.prepare("PRAGMA incremental_vacuum;")?;
+        let mut rows = stmt.query([])?;
+        while rows.next()?.is_some() {}
+        info!("Incremental vacuum finished after {:?}", start.elapsed());
+    } else {
+        // Switching vacuum mode only takes effect after one full VACUUM
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+        info!(
+            "Switched to incremental vacuum with a full VACUUM after {:?}",
+            start.elapsed()
+        );
+    }
+
+    info!("Database maintenance completed in {:?}", start.elapsed());
+    match get_storage_stats(conn) {
+        Ok(stats) => info!("Storage after maintenance: {:?}", stats),
+        Err(err) => error!("Failed to collect storage stats: {}", err),
+    }
+    Ok(())
+}
+
+/// Run maintenance once a day during the configured off-hours hour
+pub async fn schedule_maintenance(conn: Arc<Mutex<Connection>>, hour: u32, disk_guard: DiskGuard) {
+    let mut last_run: Option<NaiveDate> = None;
+    let mut interval = tokio::time::interval(MAINTENANCE_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let now = Local::now();
+        if now.hour() != hour || last_run == Some(now.date_naive()) {
+            continue;
+        }
+        // VACUUM rewrites the whole file, retry at the next check in case space frees up
+        if disk_guard.is_low_on_space() {
+            info!("Skipping database maintenance while disk space is low.");
+            continue;
+        }
+        last_run = Some(now.date_naive());
+
+        let conn = conn.lock().await;
+        if let Err(err) = run_maintenance(&conn) {
+            error!("Database maintenance failed: {}", err);
+        }
+    }
+}
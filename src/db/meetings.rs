@@ -0,0 +1,113 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+use super::activities::UsageSpan;
+
+const MEETING_EXTEND_QUERY: &str = r#"
+    UPDATE meetings SET
+        start_time = min(start_time, ?4),
+        end_time = max(end_time, ?5)
+    WHERE id = (
+        SELECT id FROM meetings
+        WHERE session_id = ?1 AND application_name = ?2 AND end_time >= ?3 AND start_time <= ?5
+        ORDER BY end_time DESC
+        LIMIT 1
+    )
+"#;
+
+const MEETING_INSERT_QUERY: &str = r#"
+    INSERT INTO meetings (session_id, application_name, start_time, end_time)
+    VALUES (?1, ?2, ?3, ?4)
+"#;
+
+/// Extend the meeting in the same app that ended less than `gap` before `usage` started,
+/// or start a new one
+pub fn record_meeting(
+    conn: &Connection,
+    usage: &UsageSpan,
+    gap: chrono::Duration,
+) -> SqliteResult<()> {
+    let extended = conn.execute(
+        MEETING_EXTEND_QUERY,
+        params![
+            usage.session_id,
+            usage.application_name,
+            usage.start_time - gap,
+            usage.start_time,
+            usage.end_time
+        ],
+    )?;
+    if extended == 0 {
+        conn.execute(
+            MEETING_INSERT_QUERY,
+            params![
+                usage.session_id,
+                usage.application_name,
+                usage.start_time,
+                usage.end_time
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+    use crate::db::test_connection;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 10)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn span(app: &str, start: NaiveDateTime, end: NaiveDateTime) -> UsageSpan {
+        UsageSpan {
+            session_id: "session".to_string(),
+            application_name: app.to_string(),
+            app_path: None,
+            title: "Meeting".to_string(),
+            start_time: start,
+            end_time: end,
+            playing_audio: true,
+        }
+    }
+
+    #[test]
+    fn spans_within_the_gap_join_one_meeting() {
+        let conn = test_connection();
+        let gap = chrono::Duration::minutes(5);
+        let spans = [
+            span("teams.exe", at(9, 0), at(9, 30)),
+            span("teams.exe", at(9, 33), at(9, 40)),
+            span("zoom.exe", at(9, 35), at(9, 45)),
+            span("teams.exe", at(10, 0), at(10, 10)),
+            // Re-read while still growing, which only widens the meeting
+            span("teams.exe", at(10, 0), at(10, 20)),
+        ];
+        for span in &spans {
+            record_meeting(&conn, span, gap).unwrap();
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT application_name, start_time, end_time FROM meetings
+                ORDER BY application_name, start_time",
+            )
+            .unwrap();
+        let meetings: Vec<(String, NaiveDateTime, NaiveDateTime)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        let expected = [
+            ("teams.exe", at(9, 0), at(9, 40)),
+            ("teams.exe", at(10, 0), at(10, 20)),
+            ("zoom.exe", at(9, 35), at(9, 45)),
+        ]
+        .map(|(app, start, end)| (app.to_string(), start, end));
+        assert_eq!(meetings, expected);
+    }
+}
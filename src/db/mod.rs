@@ -1,2 +1,52 @@
+pub(crate) mod activities;
 pub(crate) mod connection;
+pub(crate) mod heartbeats;
+pub(crate) mod idle_thresholds;
+pub(crate) mod maintenance;
+pub(crate) mod meetings;
 pub(crate) mod models;
+pub(crate) mod processes;
+pub(crate) mod reports;
+
+/// In-memory database with every migration applied
+#[cfg(test)]
+pub(crate) fn test_connection() -> rusqlite::Connection {
+    migrated(rusqlite::Connection::open_in_memory().expect("open in-memory database"))
+}
+
+/// Database file at `path` with every migration applied, for code that writes
+/// next to the database file
+#[cfg(test)]
+pub(crate) fn test_connection_at(path: &std::path::Path) -> rusqlite::Connection {
+    migrated(rusqlite::Connection::open(path).expect("open database file"))
+}
+
+#[cfg(test)]
+fn migrated(conn: rusqlite::Connection) -> rusqlite::Connection {
+    const MIGRATIONS: &[&str] = &[
+        include_str!("../../migrations/2024-11-23-135704_app/up.sql"),
+        include_str!("../../migrations/2024-11-23-135740_app_usage/up.sql"),
+        include_str!("../../migrations/2026-10-16-090000_app_usage_title_fields/up.sql"),
+        include_str!("../../migrations/2026-10-16-091000_sessions/up.sql"),
+        include_str!("../../migrations/2026-10-16-092000_app_usage_playing_audio/up.sql"),
+        include_str!("../../migrations/2026-10-16-093000_app_versions/up.sql"),
+        include_str!("../../migrations/2026-10-16-094000_session_times/up.sql"),
+        include_str!("../../migrations/2026-10-16-095000_machine_sessions/up.sql"),
+        include_str!("../../migrations/2026-10-16-096000_app_usage_remote_session/up.sql"),
+        include_str!("../../migrations/2026-10-16-097000_activities/up.sql"),
+        include_str!("../../migrations/2026-10-16-098000_document_usage_totals/up.sql"),
+        include_str!("../../migrations/2026-10-16-099000_process_lifetimes/up.sql"),
+        include_str!("../../migrations/2026-10-16-100000_app_usage_focused/up.sql"),
+        include_str!("../../migrations/2026-10-16-101000_meetings/up.sql"),
+        include_str!("../../migrations/2026-10-16-102000_idle_thresholds/up.sql"),
+        include_str!("../../migrations/2026-10-16-103000_tracker_heartbeats/up.sql"),
+        include_str!("../../migrations/2026-10-16-104000_app_usage_title_index/up.sql"),
+        include_str!("../../migrations/2026-10-16-105000_app_usage_repository/up.sql"),
+        include_str!("../../migrations/2026-10-16-106000_local_usage_dates/up.sql"),
+        include_str!("../../migrations/2026-10-16-107000_drop_repository_usage_totals/up.sql"),
+    ];
+    for migration in MIGRATIONS {
+        conn.execute_batch(migration).expect("apply migration");
+    }
+    conn
+}
@@ -1,9 +1,14 @@
 use chrono::{NaiveDate, NaiveDateTime};
 
+/// Title stored on the synthetic rows that cover idle periods
+pub const IDLE_WINDOW_TITLE: &str = "Idle";
+
 #[derive(Debug, Default, Clone)]
 pub struct App {
     pub name: String,
     pub path: String,
+    /// File version of the executable, when it has a version resource
+    pub version: Option<String>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -14,10 +19,45 @@ pub struct AppUsage {
     pub current_screen_title: String,
     pub start_time: NaiveDateTime,
     pub last_updated_time: NaiveDateTime,
+    pub project: Option<String>,
+    pub file: Option<String>,
+    pub repository: Option<String>,
+    pub branch: Option<String>,
+    /// Set once the app played audio at any point while this row was open
+    pub playing_audio: bool,
+    /// Recorded while the desktop was used over Remote Desktop
+    pub remote_session: bool,
+    /// The window was in the foreground for the whole row
+    pub focused: bool,
+}
+
+/// Lifetime of a watched process, tracked apart from window-based usage
+#[derive(Debug, Default, Clone)]
+pub struct ProcessLifetime {
+    pub id: String,
+    pub session_id: String,
+    pub process_id: u32,
+    pub exe_name: String,
+    pub start_time: NaiveDateTime,
+    /// Last time the process was seen running
+    pub end_time: NaiveDateTime,
+}
+
+/// Typical gap between inputs while an app was focused
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LearnedIdleThreshold {
+    pub typical_gap_seconds: f64,
+    pub samples: u32,
 }
 
 #[derive(Debug, Default)]
 pub struct Sessions {
     pub id: String,
     pub session_date: NaiveDate,
+    pub user_name: String,
+    pub start_time: NaiveDateTime,
+    /// Last time data was written for this session
+    pub end_time: NaiveDateTime,
+    /// When the machine booted, used to only resume sessions from the same boot
+    pub boot_time: NaiveDateTime,
 }
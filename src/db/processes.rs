@@ -0,0 +1,26 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+use super::models::ProcessLifetime;
+
+const PROCESS_LIFETIME_UPSERT_QUERY: &str = r#"
+    INSERT INTO process_lifetimes (id, session_id, process_id, exe_name, start_time, end_time)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ON CONFLICT(id) DO UPDATE SET
+        end_time = excluded.end_time
+"#;
+
+/// Insert a watched process or extend the time it was last seen
+pub fn upsert_process_lifetime(conn: &Connection, lifetime: &ProcessLifetime) -> SqliteResult<()> {
+    conn.execute(
+        PROCESS_LIFETIME_UPSERT_QUERY,
+        params![
+            lifetime.id,
+            lifetime.session_id,
+            lifetime.process_id,
+            lifetime.exe_name,
+            lifetime.start_time,
+            lifetime.end_time,
+        ],
+    )?;
+    Ok(())
+}
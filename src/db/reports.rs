@@ -0,0 +1,816 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::Serialize;
+
+use super::models::IDLE_WINDOW_TITLE;
+
+const APP_TOTALS_SINCE_QUERY: &str = r#"
+    SELECT
+        application_name,
+        SUM(
+            (julianday(last_updated_time) - julianday(max(start_time, ?1))) * 86400.0
+                * CASE WHEN focused THEN 1.0 ELSE ?2 END
+        ) AS seconds
+    FROM app_usages
+    WHERE last_updated_time > ?1 AND current_screen_title != ?3
+    GROUP BY application_name
+    ORDER BY seconds DESC
+"#;
+
+const SCREEN_TIME_ROWS_QUERY: &str = r#"
+    SELECT
+        start_time,
+        last_updated_time,
+        current_screen_title = ?3 AS is_idle
+    FROM app_usages
+    WHERE last_updated_time > ?1 AND start_time < ?2
+"#;
+
+const HEARTBEAT_COUNT_QUERY: &str = r#"
+    SELECT COUNT(*) AS minutes
+    FROM tracker_heartbeats
+    WHERE minute >= ?1 AND minute < ?2
+"#;
+
+const HEARTBEATS_BETWEEN_QUERY: &str = r#"
+    SELECT minute
+    FROM tracker_heartbeats
+    WHERE minute >= ?1 AND minute < ?2
+    ORDER BY minute
+"#;
+
+const RECENT_TITLES_QUERY: &str = r#"
+    SELECT current_screen_title, MAX(last_updated_time) AS last_seen
+    FROM app_usages
+    WHERE application_name = ?1 AND current_screen_title != ?3
+    GROUP BY current_screen_title
+    ORDER BY last_seen DESC
+    LIMIT ?2
+"#;
+
+const APP_ROWS_BETWEEN_QUERY: &str = r#"
+    SELECT start_time, last_updated_time, focused
+    FROM app_usages
+    WHERE application_name = ?1 AND last_updated_time > ?2 AND start_time < ?3
+        AND current_screen_title != ?4
+"#;
+
+const ACTIVITY_TOTALS_QUERY: &str = r#"
+    SELECT
+        name,
+        SUM((julianday(min(end_time, ?2)) - julianday(max(start_time, ?1))) * 86400.0) AS seconds
+    FROM activities
+    WHERE end_time > ?1 AND start_time < ?2
+    GROUP BY name
+    ORDER BY seconds DESC
+"#;
+
+const MEETING_TOTALS_QUERY: &str = r#"
+    SELECT
+        application_name,
+        COUNT(*) AS meetings,
+        SUM((julianday(min(end_time, ?2)) - julianday(max(start_time, ?1))) * 86400.0) AS seconds
+    FROM meetings
+    WHERE end_time > ?1 AND start_time < ?2
+    GROUP BY application_name
+    ORDER BY seconds DESC
+"#;
+
+/// Hours listed as an app's peak hours in a comparison
+const PEAK_HOURS: usize = 3;
+
+const REPOSITORY_ROWS_BETWEEN_QUERY: &str = r#"
+    SELECT repository, start_time, last_updated_time
+    FROM app_usages
+    WHERE repository IS NOT NULL AND last_updated_time > ?1 AND start_time < ?2
+        AND current_screen_title != ?3
+"#;
+
+/// Quarter hours searched past a day boundary that falls in a DST gap
+const DST_GAP_STEPS: i64 = 4 * 4;
+
+/// Missing heartbeats shorter than this are jitter rather than a gap
+const MIN_GAP_MINUTES: i64 = 2;
+
+/// Time spent in a single application
+#[derive(Debug, Clone, Serialize)]
+pub struct AppTotal {
+    pub application_name: String,
+    pub seconds: u64,
+}
+
+/// Share of a reporting day the tracker was running for
+#[derive(Debug, Clone, Serialize)]
+pub struct DayCoverage {
+    pub date: NaiveDate,
+    /// Minutes of the day with a tracker heartbeat
+    pub covered_minutes: u64,
+    /// Minutes of the day that have passed, the whole day unless it is today
+    pub elapsed_minutes: u64,
+    pub coverage_percent: f64,
+}
+
+/// Time spent in one activity from the activity rules
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityTotal {
+    pub name: String,
+    pub seconds: u64,
+}
+
+/// Time spent in meetings held in one conferencing app or browser
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingTotal {
+    pub application_name: String,
+    pub meetings: u64,
+    pub seconds: u64,
+}
+
+/// Coding time in one repository on one reporting day
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryTotal {
+    pub date: NaiveDate,
+    pub repository: String,
+    pub seconds: u64,
+}
+
+/// Usage of one app in a side-by-side comparison
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSeries {
+    pub application_name: String,
+    /// Seconds per day, aligned with `AppComparison::days`
+    pub daily_seconds: Vec<u64>,
+    pub total_seconds: u64,
+    /// Share of the app's time its window was focused rather than just visible
+    pub focused_percent: f64,
+    /// Local hours of the day with the most use, busiest first
+    pub peak_hours: Vec<u32>,
+}
+
+/// Two apps' usage over the same days
+#[derive(Debug, Clone, Serialize)]
+pub struct AppComparison {
+    /// Reporting days covered, oldest first
+    pub days: Vec<NaiveDate>,
+    pub apps: Vec<AppSeries>,
+}
+
+/// Period without heartbeats, when the tracker or the machine wasn't running
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DataGap {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+/// Where reporting days and weeks begin. Night owls can roll the day over
+/// after midnight, and weeks can start on any day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayBoundary {
+    /// Local hour a new day starts at
+    pub hour: u32,
+    pub week_start: Weekday,
+}
+
+impl Default for DayBoundary {
+    fn default() -> Self {
+        Self {
+            hour: 0,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl DayBoundary {
+    /// Reporting day the current local time falls in
+    pub fn today(&self) -> NaiveDate {
+        self.day_of_local(Local::now().naive_local())
+    }
+
+    /// Reporting day a stored UTC timestamp falls in
+    pub fn day_of(&self, time: NaiveDateTime) -> NaiveDate {
+        self.day_of_local(Local.from_utc_datetime(&time).naive_local())
+    }
+
+    fn day_of_local(&self, time: NaiveDateTime) -> NaiveDate {
+        (time - chrono::Duration::hours(self.hour.into())).date()
+    }
+
+    /// Start of the current reporting day, expressed in UTC like the stored timestamps
+    pub fn start_of_today(&self) -> NaiveDateTime {
+        self.start_of_day(self.today())
+    }
+
+    /// Start of the current reporting week, expressed in UTC like the stored timestamps
+    pub fn start_of_week(&self) -> NaiveDateTime {
+        let today = self.today();
+        let days_into_week = today.weekday().days_since(self.week_start);
+        self.start_of_day(today - chrono::Days::new(days_into_week.into()))
+    }
+
+    /// Start of a reporting day, expressed in UTC like the stored timestamps. If
+    /// a DST change skips the boundary hour, the day starts when the clocks do,
+    /// and if it repeats the hour, the day starts at its first occurrence.
+    pub fn start_of_day(&self, date: NaiveDate) -> NaiveDateTime {
+        let start =
+            date.and_time(NaiveTime::from_hms_opt(self.hour, 0, 0).unwrap_or(NaiveTime::MIN));
+        // Transitions fall on quarter hours, and no gap is longer than a few hours
+        (0..=DST_GAP_STEPS)
+            .find_map(|step| {
+                let local = start + chrono::Duration::minutes(15 * step);
+                // Repeated times aren't always listed in order, and the instant of a
+                // transition can be listed though the clocks never show it there
+                let times = Local.from_local_datetime(&local);
+                [times.earliest(), times.latest()]
+                    .into_iter()
+                    .flatten()
+                    .map(|time| time.naive_utc())
+                    .filter(|time| Local.from_utc_datetime(time).naive_local() == local)
+                    .min()
+            })
+            .unwrap_or(start)
+    }
+}
+
+/// Per-app totals of non-idle use since `since`, largest first. Rows that
+/// started earlier count from `since`, and time in visible but unfocused
+/// windows counts `unfocused_weight` per second.
+pub fn app_totals_since(
+    conn: &Connection,
+    since: NaiveDateTime,
+    unfocused_weight: f64,
+) -> SqliteResult<Vec<AppTotal>> {
+    let mut stmt = conn.prepare(APP_TOTALS_SINCE_QUERY)?;
+    let rows = stmt.query_map(params![since, unfocused_weight, IDLE_WINDOW_TITLE], |row| {
+        Ok(AppTotal {
+            application_name: row.get(0)?,
+            seconds: row.get::<_, f64>(1)?.max(0.0) as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Distinct window titles recorded for an app, most recently seen first
+pub fn recent_titles(
+    conn: &Connection,
+    application_name: &str,
+    limit: u32,
+) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare(RECENT_TITLES_QUERY)?;
+    let rows = stmt.query_map(params![application_name, limit, IDLE_WINDOW_TITLE], |row| {
+        row.get(0)
+    })?;
+    rows.collect()
+}
+
+/// Per-activity totals between `since` and `until`, largest first
+pub fn activity_totals(
+    conn: &Connection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> SqliteResult<Vec<ActivityTotal>> {
+    let mut stmt = conn.prepare(ACTIVITY_TOTALS_QUERY)?;
+    let rows = stmt.query_map(params![since, until], |row| {
+        Ok(ActivityTotal {
+            name: row.get(0)?,
+            seconds: row.get::<_, f64>(1)?.max(0.0) as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Per-app meeting counts and time between `since` and `until`, largest first
+pub fn meeting_totals(
+    conn: &Connection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> SqliteResult<Vec<MeetingTotal>> {
+    let mut stmt = conn.prepare(MEETING_TOTALS_QUERY)?;
+    let rows = stmt.query_map(params![since, until], |row| {
+        Ok(MeetingTotal {
+            application_name: row.get(0)?,
+            meetings: row.get(1)?,
+            seconds: row.get::<_, f64>(2)?.max(0.0) as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Time per repository and reporting day between `since` and `until`, by day
+/// and then largest first. Windows open on the same repository at once, such as
+/// an editor and a terminal, count once.
+pub fn repository_totals(
+    conn: &Connection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+    boundary: DayBoundary,
+) -> SqliteResult<Vec<RepositoryTotal>> {
+    let mut stmt = conn.prepare(REPOSITORY_ROWS_BETWEEN_QUERY)?;
+    let rows = stmt
+        .query_map(params![since, until, IDLE_WINDOW_TITLE], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, NaiveDateTime>(1)?.max(since),
+                row.get::<_, NaiveDateTime>(2)?.min(until),
+            ))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    // Split rows at day boundaries so each day's intervals can be merged on their own
+    let mut intervals: BTreeMap<(NaiveDate, String), Vec<_>> = BTreeMap::new();
+    for (repository, start, end) in rows {
+        let mut chunk_start = start;
+        while chunk_start < end {
+            let date = boundary.day_of(chunk_start);
+            let next_day = date.succ_opt().map(|next| boundary.start_of_day(next));
+            let chunk_end = next_day
+                .filter(|next_day| *next_day > chunk_start)
+                .map_or(end, |next_day| next_day.min(end));
+            intervals
+                .entry((date, repository.clone()))
+                .or_default()
+                .push((chunk_start, chunk_end));
+            chunk_start = chunk_end;
+        }
+    }
+
+    let mut totals: Vec<RepositoryTotal> = intervals
+        .into_iter()
+        .map(|((date, repository), intervals)| {
+            let seconds: f64 = merge_intervals(intervals.into_iter())
+                .iter()
+                .map(|(start, end)| (*end - *start).num_milliseconds() as f64 / 1000.0)
+                .sum();
+            RepositoryTotal {
+                date,
+                repository,
+                seconds: seconds as u64,
+            }
+        })
+        .collect();
+    totals.sort_by(|a, b| a.date.cmp(&b.date).then(b.seconds.cmp(&a.seconds)));
+    Ok(totals)
+}
+
+/// Compare two apps over the last `days` reporting days, today included
+pub fn compare_apps(
+    conn: &Connection,
+    app_a: &str,
+    app_b: &str,
+    days: u32,
+    boundary: DayBoundary,
+) -> SqliteResult<AppComparison> {
+    let today = boundary.today();
+    let first_day = today
+        .checked_sub_days(chrono::Days::new(days.saturating_sub(1).into()))
+        .unwrap_or(today);
+    let days: Vec<NaiveDate> = first_day
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .collect();
+    let since = boundary.start_of_day(first_day);
+    let until = Local::now().naive_utc();
+
+    let apps = [app_a, app_b]
+        .into_iter()
+        .map(|app| app_series(conn, app, &days, since, until, boundary))
+        .collect::<SqliteResult<_>>()?;
+    Ok(AppComparison { days, apps })
+}
+
+fn app_series(
+    conn: &Connection,
+    application_name: &str,
+    days: &[NaiveDate],
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+    boundary: DayBoundary,
+) -> SqliteResult<AppSeries> {
+    let mut stmt = conn.prepare(APP_ROWS_BETWEEN_QUERY)?;
+    let rows = stmt
+        .query_map(
+            params![application_name, since, until, IDLE_WINDOW_TITLE],
+            |row| {
+                Ok((
+                    row.get::<_, NaiveDateTime>(0)?.max(since),
+                    row.get::<_, NaiveDateTime>(1)?.min(until),
+                    row.get::<_, bool>(2)?,
+                ))
+            },
+        )?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut daily_seconds = vec![0.0; days.len()];
+    let mut hourly_seconds = [0.0; 24];
+    let mut focused_seconds = 0.0;
+    for (start, end, focused) in rows {
+        // Split rows on local hour boundaries so long rows land in the right day and
+        // hour, which aren't UTC hour boundaries in zones with a half-hour offset
+        let mut chunk_start = start;
+        while chunk_start < end {
+            let local = Local.from_utc_datetime(&chunk_start);
+            let into_hour =
+                chrono::Duration::seconds(i64::from(local.minute() * 60 + local.second()))
+                    + chrono::Duration::nanoseconds(i64::from(local.nanosecond()));
+            let next_hour = chunk_start - into_hour + chrono::Duration::hours(1);
+            let chunk_end = next_hour.min(end);
+            let seconds = (chunk_end - chunk_start).num_milliseconds() as f64 / 1000.0;
+
+            if let Ok(index) = days.binary_search(&boundary.day_of(chunk_start)) {
+                daily_seconds[index] += seconds;
+            }
+            hourly_seconds[local.hour() as usize] += seconds;
+            if focused {
+                focused_seconds += seconds;
+            }
+            chunk_start = chunk_end;
+        }
+    }
+
+    let total_seconds: f64 = daily_seconds.iter().sum();
+    let mut peak_hours: Vec<u32> = (0..24)
+        .filter(|hour| hourly_seconds[*hour as usize] > 0.0)
+        .collect();
+    peak_hours.sort_by(|a, b| hourly_seconds[*b as usize].total_cmp(&hourly_seconds[*a as usize]));
+    peak_hours.truncate(PEAK_HOURS);
+
+    Ok(AppSeries {
+        application_name: application_name.to_string(),
+        daily_seconds: daily_seconds
+            .into_iter()
+            .map(|seconds| seconds as u64)
+            .collect(),
+        total_seconds: total_seconds as u64,
+        focused_percent: if total_seconds > 0.0 {
+            (focused_seconds * 100.0 / total_seconds).min(100.0)
+        } else {
+            0.0
+        },
+        peak_hours,
+    })
+}
+
+/// Heartbeat coverage of the last `days` reporting days, today first. Days the
+/// tracker wasn't running for all of have incomplete data in every report.
+pub fn daily_coverage(
+    conn: &Connection,
+    days: u32,
+    boundary: DayBoundary,
+) -> SqliteResult<Vec<DayCoverage>> {
+    let now = Local::now().naive_utc();
+    let today = boundary.today();
+    let mut stmt = conn.prepare(HEARTBEAT_COUNT_QUERY)?;
+    (0..days)
+        .filter_map(|ago| today.checked_sub_days(chrono::Days::new(ago.into())))
+        .map(|date| {
+            let start = boundary.start_of_day(date);
+            let end = date
+                .succ_opt()
+                .map(|next| boundary.start_of_day(next))
+                .unwrap_or(now)
+                .min(now);
+            let elapsed_minutes = ((end - start).num_seconds().max(0) as u64).div_ceil(60);
+            let covered_minutes: u64 = stmt.query_row(params![start, end], |row| row.get(0))?;
+            let coverage_percent = if elapsed_minutes == 0 {
+                100.0
+            } else {
+                (covered_minutes as f64 * 100.0 / elapsed_minutes as f64).min(100.0)
+            };
+            Ok(DayCoverage {
+                date,
+                covered_minutes,
+                elapsed_minutes,
+                coverage_percent,
+            })
+        })
+        .collect()
+}
+
+/// Periods between `since` and `until` with no heartbeat, such as crashes or
+/// power loss, so reports can show them as missing data rather than zero use
+pub fn data_gaps(
+    conn: &Connection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> SqliteResult<Vec<DataGap>> {
+    let mut stmt = conn.prepare(HEARTBEATS_BETWEEN_QUERY)?;
+    let minutes = stmt
+        .query_map(params![since, until], |row| row.get::<_, NaiveDateTime>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    // Each heartbeat covers its minute, anything between the covered minutes is a gap
+    let min_gap = chrono::Duration::minutes(MIN_GAP_MINUTES);
+    let mut gaps = Vec::new();
+    let mut covered_until = since;
+    for minute in minutes {
+        if minute - covered_until >= min_gap {
+            gaps.push(DataGap {
+                start: covered_until,
+                end: minute,
+            });
+        }
+        covered_until = covered_until.max(minute + chrono::Duration::minutes(1));
+    }
+    if until - covered_until >= min_gap {
+        gaps.push(DataGap {
+            start: covered_until,
+            end: until,
+        });
+    }
+    Ok(gaps)
+}
+
+/// Wall-clock seconds between `since` and `until` in which any window was in
+/// use. Overlapping rows are counted once and idle periods are left out, so
+/// unlike the per-app totals this never exceeds the elapsed time.
+pub fn total_screen_time(
+    conn: &Connection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> SqliteResult<u64> {
+    let mut stmt = conn.prepare(SCREEN_TIME_ROWS_QUERY)?;
+    let rows = stmt
+        .query_map(params![since, until, IDLE_WINDOW_TITLE], |row| {
+            Ok((
+                row.get::<_, NaiveDateTime>(0)?.max(since),
+                row.get::<_, NaiveDateTime>(1)?.min(until),
+                row.get::<_, bool>(2)?,
+            ))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let (idle, active): (Vec<_>, Vec<_>) = rows.into_iter().partition(|(_, _, is_idle)| *is_idle);
+    let active = merge_intervals(active.into_iter().map(|(start, end, _)| (start, end)));
+    let idle = merge_intervals(idle.into_iter().map(|(start, end, _)| (start, end)));
+
+    let active_seconds: f64 = active
+        .iter()
+        .map(|(start, end)| (*end - *start).num_milliseconds() as f64 / 1000.0)
+        .sum();
+    Ok((active_seconds - overlap_seconds(&active, &idle)).max(0.0) as u64)
+}
+
+/// Sort intervals and join the ones that overlap or touch
+fn merge_intervals(
+    intervals: impl Iterator<Item = (NaiveDateTime, NaiveDateTime)>,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut intervals: Vec<_> = intervals.filter(|(start, end)| start < end).collect();
+    intervals.sort();
+    let mut merged: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Seconds covered by both lists of merged, sorted intervals
+fn overlap_seconds(
+    left: &[(NaiveDateTime, NaiveDateTime)],
+    right: &[(NaiveDateTime, NaiveDateTime)],
+) -> f64 {
+    let (mut i, mut j, mut seconds) = (0, 0, 0.0);
+    while i < left.len() && j < right.len() {
+        let start = left[i].0.max(right[j].0);
+        let end = left[i].1.min(right[j].1);
+        if start < end {
+            seconds += (end - start).num_milliseconds() as f64 / 1000.0;
+        }
+        if left[i].1 < right[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, Duration};
+
+    use super::*;
+    use crate::db::heartbeats::record_heartbeat;
+    use crate::db::test_connection;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 10)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn insert_usage(
+        conn: &Connection,
+        app: &str,
+        title: &str,
+        (start, end): (NaiveDateTime, NaiveDateTime),
+        focused: bool,
+        repository: Option<&str>,
+    ) {
+        conn.execute(
+            "INSERT OR IGNORE INTO apps (name, path) VALUES (?1, ?1)",
+            params![app],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO app_usages (id, session_id, application_name, current_screen_title,
+                start_time, last_updated_time, focused, repository)
+            VALUES (lower(hex(randomblob(16))), 'session', ?1, ?2, ?3, ?4, ?5, ?6)",
+            params![app, title, start, end, focused, repository],
+        )
+        .unwrap();
+    }
+
+    /// SQLite sums julianday differences, which can land a hair under a whole second
+    fn assert_seconds(actual: u64, expected: u64) {
+        assert!(actual.abs_diff(expected) <= 1, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn app_totals_skip_idle_rows_and_clip_to_since() {
+        let conn = test_connection();
+        insert_usage(
+            &conn,
+            "code.exe",
+            "main.rs",
+            (at(8, 0), at(10, 0)),
+            true,
+            None,
+        );
+        insert_usage(
+            &conn,
+            "code.exe",
+            IDLE_WINDOW_TITLE,
+            (at(10, 0), at(10, 30)),
+            false,
+            None,
+        );
+        insert_usage(
+            &conn,
+            "chrome.exe",
+            "Docs",
+            (at(9, 30), at(10, 0)),
+            false,
+            None,
+        );
+
+        let totals = app_totals_since(&conn, at(9, 0), 0.5).unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].application_name, "code.exe");
+        assert_seconds(totals[0].seconds, 3600);
+        assert_eq!(totals[1].application_name, "chrome.exe");
+        assert_seconds(totals[1].seconds, 900);
+    }
+
+    #[test]
+    fn merge_intervals_joins_overlapping_and_touching() {
+        let merged = merge_intervals(
+            [
+                (at(10, 0), at(10, 30)),
+                (at(9, 0), at(9, 15)),
+                (at(10, 30), at(11, 0)),
+                (at(9, 10), at(9, 20)),
+                (at(12, 0), at(12, 0)),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(merged, [(at(9, 0), at(9, 20)), (at(10, 0), at(11, 0))]);
+    }
+
+    #[test]
+    fn overlap_seconds_counts_shared_time() {
+        let left = [(at(9, 0), at(10, 0)), (at(11, 0), at(12, 0))];
+        let right = [(at(9, 30), at(11, 30))];
+        assert_eq!(overlap_seconds(&left, &right), 3600.0);
+        assert_eq!(overlap_seconds(&left, &[]), 0.0);
+    }
+
+    #[test]
+    fn meeting_totals_clip_to_the_range() {
+        let conn = test_connection();
+        let meetings = [
+            ("teams.exe", at(9, 0), at(9, 40)),
+            ("teams.exe", at(10, 0), at(10, 20)),
+            ("zoom.exe", at(9, 35), at(9, 45)),
+            ("zoom.exe", at(13, 0), at(14, 0)),
+        ];
+        for (app, start, end) in meetings {
+            conn.execute(
+                "INSERT INTO meetings (session_id, application_name, start_time, end_time)
+                VALUES ('session', ?1, ?2, ?3)",
+                params![app, start, end],
+            )
+            .unwrap();
+        }
+
+        let totals = meeting_totals(&conn, at(9, 10), at(12, 0)).unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].application_name, "teams.exe");
+        assert_eq!(totals[0].meetings, 2);
+        assert_seconds(totals[0].seconds, 3000);
+        assert_eq!(totals[1].application_name, "zoom.exe");
+        assert_eq!(totals[1].meetings, 1);
+        assert_seconds(totals[1].seconds, 600);
+    }
+
+    #[test]
+    fn daily_coverage_counts_heartbeats_per_day() {
+        let conn = test_connection();
+        let boundary = DayBoundary::default();
+        let today = boundary.today();
+        let yesterday = today.pred_opt().unwrap();
+        let start = boundary.start_of_day(yesterday);
+        for minute in 0..600 {
+            record_heartbeat(&conn, start + Duration::minutes(minute)).unwrap();
+        }
+
+        let coverage = daily_coverage(&conn, 2, boundary).unwrap();
+        assert_eq!(coverage.len(), 2);
+        assert_eq!(coverage[1].date, yesterday);
+        assert_eq!(coverage[1].covered_minutes, 600);
+        let day_minutes = (boundary.start_of_day(today) - start).num_minutes() as u64;
+        assert_eq!(coverage[1].elapsed_minutes, day_minutes);
+    }
+
+    #[test]
+    fn data_gaps_ignore_jitter() {
+        let conn = test_connection();
+        let minutes = (0..=10).chain(12..30).chain(45..58);
+        for minute in minutes {
+            record_heartbeat(&conn, at(9, minute) + Duration::seconds(20)).unwrap();
+        }
+        let gaps: Vec<_> = data_gaps(&conn, at(9, 0), at(10, 0))
+            .unwrap()
+            .into_iter()
+            .map(|gap| (gap.start, gap.end))
+            .collect();
+        assert_eq!(gaps, [(at(9, 30), at(9, 45)), (at(9, 58), at(10, 0))]);
+    }
+
+    /// Walks two years so DST transitions in whatever zone the tests run in are covered
+    #[test]
+    fn reporting_days_contain_their_instants() {
+        for hour in [0, 3, 23] {
+            let boundary = DayBoundary {
+                hour,
+                week_start: Weekday::Mon,
+            };
+            let mut time = NaiveDate::from_ymd_opt(2025, 1, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .unwrap();
+            let end = time + Duration::days(2 * 365);
+            while time < end {
+                let day = boundary.day_of(time);
+                let start = boundary.start_of_day(day);
+                let next_start = boundary.start_of_day(day.succ_opt().unwrap());
+                assert!(start <= time && time < next_start, "{} in {}", time, day);
+                assert_eq!(boundary.day_of(start), day);
+                time += Duration::minutes(37);
+            }
+        }
+    }
+
+    #[test]
+    fn weeks_start_on_the_configured_day() {
+        let now = Local::now().naive_utc();
+        for week_start in [Weekday::Mon, Weekday::Wed, Weekday::Sun] {
+            let boundary = DayBoundary {
+                hour: 4,
+                week_start,
+            };
+            let start = boundary.start_of_week();
+            assert_eq!(boundary.day_of(start).weekday(), week_start);
+            assert!(start <= now && now - start < Duration::days(8));
+            let first_day = boundary.today() - Days::new(6);
+            assert!(boundary.day_of(start) >= first_day);
+        }
+    }
+
+    #[test]
+    fn repository_totals_count_overlapping_windows_once() {
+        let conn = test_connection();
+        let rows = [
+            ("code.exe", "main.rs - app", (at(9, 0), at(10, 0)), "app"),
+            ("wt.exe", "app (main)", (at(9, 30), at(10, 30)), "app"),
+            ("wt.exe", IDLE_WINDOW_TITLE, (at(10, 30), at(11, 0)), "app"),
+            ("code.exe", "lib.rs - lib", (at(9, 0), at(9, 15)), "lib"),
+        ];
+        for (app, title, span, repository) in rows {
+            insert_usage(&conn, app, title, span, true, Some(repository));
+        }
+
+        // Rows may straddle a local midnight, so compare totals across days
+        let totals = repository_totals(&conn, at(0, 0), at(23, 0), DayBoundary::default()).unwrap();
+        let seconds = |repository: &str| -> u64 {
+            totals
+                .iter()
+                .filter(|total| total.repository == repository)
+                .map(|total| total.seconds)
+                .sum()
+        };
+        assert_eq!(seconds("app"), 5400);
+        assert_eq!(seconds("lib"), 900);
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tracks whether the database volume is low on space so non-essential
+/// writers can pause while usage rows keep being written
+#[derive(Debug, Clone, Default)]
+pub struct DiskGuard {
+    low_space: Arc<AtomicBool>,
+}
+
+impl DiskGuard {
+    /// Whether non-essential writes should be skipped
+    pub fn is_low_on_space(&self) -> bool {
+        self.low_space.load(Ordering::Relaxed)
+    }
+
+    /// Check the free space of the volume holding `path` until the process exits,
+    /// alerting once each time it drops below `min_free_bytes`
+    pub async fn monitor(self, path: PathBuf, min_free_bytes: u64) {
+        let mut interval = tokio::time::interval(DISK_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let Some(free_bytes) = WindowsHandle::get_free_disk_space(&path) else {
+                continue;
+            };
+            let low_space = free_bytes < min_free_bytes;
+            if low_space == self.low_space.swap(low_space, Ordering::Relaxed) {
+                continue;
+            }
+
+            if low_space {
+                let message = format!(
+                    "Only {} MB is free on the drive holding {}. Activity summaries, meetings, watched processes, the widget and shared stats are paused until at least {} MB is free.",
+                    free_bytes / 1024 / 1024,
+                    path.display(),
+                    min_free_bytes / 1024 / 1024
+                );
+                error!("{}", message);
+                WindowsHandle::show_alert("Screen time tracker", &message);
+            } else {
+                info!(
+                    "Free disk space is back to {} MB, resuming paused writes.",
+                    free_bytes / 1024 / 1024
+                );
+            }
+        }
+    }
+}
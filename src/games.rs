@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use regex::Regex;
+use serde::Deserialize;
+
+/// Activity recorded for apps installed by a game launcher
+pub const GAMES_ACTIVITY: &str = "Games";
+
+/// Default Steam install locations, extra libraries are listed by Steam itself
+const STEAM_ROOTS: &[&str] = &[r"C:\Program Files (x86)\Steam", r"C:\Program Files\Steam"];
+
+/// Folder the Epic Games Launcher keeps one manifest per installed game in
+const EPIC_MANIFESTS_DIR: &str = r"Epic\EpicGamesLauncher\Data\Manifests";
+
+/// The part of an Epic manifest that says where the game is installed
+#[derive(Debug, Deserialize)]
+struct EpicManifest {
+    #[serde(rename = "InstallLocation")]
+    install_location: String,
+}
+
+/// Install folders of the games known to Steam and the Epic Games Launcher
+#[derive(Debug, Default)]
+pub struct GameLibrary {
+    /// Lowercase folder paths ending in a separator
+    install_dirs: Vec<String>,
+}
+
+impl GameLibrary {
+    /// Read the launcher manifests on this machine, launchers that aren't installed are skipped
+    pub fn scan() -> Self {
+        let mut install_dirs: Vec<String> = steam_install_dirs()
+            .into_iter()
+            .chain(epic_install_dirs())
+            .map(|dir| {
+                let mut dir = dir.to_string_lossy().to_lowercase();
+                if !dir.ends_with('\\') {
+                    dir.push('\\');
+                }
+                dir
+            })
+            .collect();
+        install_dirs.sort();
+        install_dirs.dedup();
+        info!("Found {} installed games.", install_dirs.len());
+        Self { install_dirs }
+    }
+
+    /// Whether the executable lives inside a game's install folder
+    pub fn is_game(&self, app_path: &str) -> bool {
+        let app_path = app_path.to_lowercase();
+        self.install_dirs
+            .iter()
+            .any(|dir| app_path.starts_with(dir))
+    }
+}
+
+/// Game folders from the app manifests of every Steam library
+fn steam_install_dirs() -> Vec<PathBuf> {
+    let path_pattern = Regex::new(r#""path"\s+"([^"]+)""#).expect("valid Steam path pattern");
+    let installdir_pattern =
+        Regex::new(r#""installdir"\s+"([^"]+)""#).expect("valid Steam installdir pattern");
+
+    let mut libraries: Vec<PathBuf> = Vec::new();
+    for root in STEAM_ROOTS
+        .iter()
+        .map(Path::new)
+        .filter(|root| root.exists())
+    {
+        libraries.push(root.to_path_buf());
+        let folders = root.join("steamapps").join("libraryfolders.vdf");
+        if let Ok(contents) = std::fs::read_to_string(&folders) {
+            libraries.extend(
+                path_pattern
+                    .captures_iter(&contents)
+                    .map(|captures| PathBuf::from(unescape_vdf(&captures[1]))),
+            );
+        }
+    }
+    libraries.sort();
+    libraries.dedup();
+
+    let mut install_dirs = Vec::new();
+    for library in libraries {
+        let steamapps = library.join("steamapps");
+        let Ok(entries) = std::fs::read_dir(&steamapps) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if !(name.starts_with("appmanifest_") && name.ends_with(".acf")) {
+                continue;
+            }
+            let Ok(contents) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            if let Some(captures) = installdir_pattern.captures(&contents) {
+                install_dirs.push(steamapps.join("common").join(unescape_vdf(&captures[1])));
+            }
+        }
+    }
+    debug!("Steam games: {:?}", install_dirs);
+    install_dirs
+}
+
+/// Game folders from the Epic Games Launcher's install manifests
+fn epic_install_dirs() -> Vec<PathBuf> {
+    let Some(program_data) = std::env::var_os("ProgramData") else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(Path::new(&program_data).join(EPIC_MANIFESTS_DIR)) else {
+        return Vec::new();
+    };
+    let install_dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "item"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<EpicManifest>(&contents).ok())
+        .map(|manifest| PathBuf::from(manifest.install_location))
+        .collect();
+    debug!("Epic games: {:?}", install_dirs);
+    install_dirs
+}
+
+/// VDF strings escape backslashes in paths
+fn unescape_vdf(value: &str) -> String {
+    value.replace(r"\\", r"\")
+}
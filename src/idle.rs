@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime};
+use log::{debug, error, info};
+use rusqlite::Connection;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::db::idle_thresholds::{load_idle_thresholds, save_idle_threshold};
+use crate::db::models::LearnedIdleThreshold;
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+
+const IDLE_EVENT_CAPACITY: usize = 16;
+
+/// Shorter input gaps say nothing about how long an app is used without input
+const MIN_LEARNED_GAP: Duration = Duration::from_secs(30);
+
+/// Weight of the newest gap in an app's moving average
+const LEARNING_RATE: f64 = 0.1;
+
+/// A learned threshold sits this many typical gaps after the last input
+const THRESHOLD_MARGIN: f64 = 2.0;
+
+/// Gaps an app needs before its learned threshold replaces the base one
+const MIN_LEARNED_SAMPLES: u32 = 20;
+
+/// Idle state transitions broadcast to every subscriber
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdleEvent {
+    /// No input for at least the idle threshold; carries the time since last input
+    Started { idle_for: Duration },
+    /// Input resumed after an idle period; carries the total idle duration and
+    /// the UTC time of the input that ended it
+    Ended {
+        idle_for: Duration,
+        resumed_at: NaiveDateTime,
+    },
+}
+
+/// Central idle detector so consumers don't poll the last input time themselves
+pub struct IdleMonitor {
+    threshold: Duration,
+    poll_interval: Duration,
+    tx: broadcast::Sender<IdleEvent>,
+    learning: Option<IdleLearning>,
+}
+
+impl IdleMonitor {
+    pub fn new(threshold: Duration, poll_interval: Duration) -> Self {
+        let (tx, _) = broadcast::channel(IDLE_EVENT_CAPACITY);
+        Self {
+            threshold,
+            poll_interval,
+            tx,
+            learning: None,
+        }
+    }
+
+    /// Adjust the threshold per app from the input gaps seen while it is focused
+    pub fn with_learning(mut self, learning: IdleLearning) -> Self {
+        self.learning = Some(learning);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<IdleEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Poll the last input time and broadcast transitions until every subscriber is gone
+    pub async fn run(mut self) {
+        let mut idle_for: Option<Duration> = None;
+        let mut previous_since_input = Duration::ZERO;
+        let mut focused_app: Option<String> = None;
+        let mut interval = tokio::time::interval(self.poll_interval);
+
+        loop {
+            interval.tick().await;
+            if self.tx.receiver_count() == 0 {
+                info!("No idle subscribers left, stopping idle monitor.");
+                break;
+            }
+
+            let since_input = match WindowsHandle::get_last_input_info() {
+                Ok(duration) => duration,
+                Err(_) => continue,
+            };
+
+            let mut threshold = self.threshold;
+            if let Some(learning) = self.learning.as_mut() {
+                // Input arrived since the last poll, ending a gap in the app focused during it
+                if since_input < previous_since_input {
+                    if let Some(app) = &focused_app {
+                        learning
+                            .record_gap(app, previous_since_input, self.threshold)
+                            .await;
+                    }
+                }
+                focused_app = WindowsHandle::get_foreground_app();
+                if let Some(app) = &focused_app {
+                    threshold = learning.threshold_for(app, self.threshold);
+                }
+            }
+            previous_since_input = since_input;
+
+            let event = match idle_for {
+                None if since_input >= threshold => Some(IdleEvent::Started {
+                    idle_for: since_input,
+                }),
+                Some(previous) if since_input < threshold => {
+                    // The last sample before input resumed is the closest idle length we know,
+                    // while the last input time pins down when it resumed
+                    let since_input = chrono::Duration::from_std(since_input).unwrap_or_default();
+                    Some(IdleEvent::Ended {
+                        idle_for: previous,
+                        resumed_at: Local::now().naive_utc() - since_input,
+                    })
+                }
+                _ => None,
+            };
+
+            idle_for = if since_input >= threshold {
+                Some(since_input)
+            } else {
+                None
+            };
+
+            if let Some(event) = event {
+                debug!("Idle state changed: {:?}", event);
+                let _ = self.tx.send(event);
+            }
+        }
+    }
+}
+
+/// Idle thresholds learned per app, kept in the database so they can be inspected
+pub struct IdleLearning {
+    conn: Arc<Mutex<Connection>>,
+    max_threshold: Duration,
+    learned: HashMap<String, LearnedIdleThreshold>,
+}
+
+impl IdleLearning {
+    /// Start from the gaps learned in earlier runs; thresholds never exceed `max_threshold`
+    pub async fn load(conn: Arc<Mutex<Connection>>, max_threshold: Duration) -> Self {
+        let learned = load_idle_thresholds(&*conn.lock().await).unwrap_or_else(|err| {
+            error!("Failed to load learned idle thresholds: {}", err);
+            HashMap::new()
+        });
+        info!("Loaded learned idle thresholds for {} apps.", learned.len());
+        Self {
+            conn,
+            max_threshold,
+            learned,
+        }
+    }
+
+    /// Threshold for `app`, never below `base` or above the configured maximum
+    fn threshold_for(&self, app: &str, base: Duration) -> Duration {
+        match self.learned.get(app) {
+            Some(learned) if learned.samples >= MIN_LEARNED_SAMPLES => {
+                Duration::from_secs_f64(learned.typical_gap_seconds * THRESHOLD_MARGIN)
+                    .clamp(base, self.max_threshold.max(base))
+            }
+            _ => base,
+        }
+    }
+
+    /// Fold an input gap into the app's moving average and store the result
+    async fn record_gap(&mut self, app: &str, gap: Duration, base: Duration) {
+        // A gap past the threshold in effect was an idle period, not reading or watching
+        if gap < MIN_LEARNED_GAP || gap >= self.threshold_for(app, base) {
+            return;
+        }
+        let learned = self.learned.entry(app.to_string()).or_default();
+        let base_gap = base.as_secs_f64() / THRESHOLD_MARGIN;
+        if learned.samples == 0 {
+            // Start from the gap the base threshold implies so one long gap can't set it
+            learned.typical_gap_seconds = base_gap;
+        }
+        // Gaps past the threshold are never seen, which biases the average toward
+        // short gaps, so it is kept at or above the gap the base threshold implies
+        let gap = gap.as_secs_f64();
+        learned.typical_gap_seconds = (learned.typical_gap_seconds
+            + (gap - learned.typical_gap_seconds) * LEARNING_RATE)
+            .max(base_gap);
+        learned.samples = learned.samples.saturating_add(1);
+        let learned = *learned;
+
+        let threshold = self.threshold_for(app, base);
+        debug!(
+            "Learned input gap for {}: {:.0}s, idle threshold {:?}",
+            app, learned.typical_gap_seconds, threshold
+        );
+        let now = Local::now().naive_utc();
+        let conn = self.conn.lock().await;
+        if let Err(err) = save_idle_threshold(&conn, app, &learned, threshold.as_secs(), now) {
+            error!("Failed to save learned idle threshold for {}: {}", app, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_connection;
+
+    const BASE: Duration = Duration::from_secs(300);
+
+    #[tokio::test]
+    async fn short_gaps_do_not_pull_the_threshold_below_the_base() {
+        let conn = Arc::new(Mutex::new(test_connection()));
+        let mut learning = IdleLearning::load(conn, Duration::from_secs(1800)).await;
+        for i in 0..100 {
+            let gap = if i % 4 == 0 { 280 } else { 40 };
+            learning
+                .record_gap("code.exe", Duration::from_secs(gap), BASE)
+                .await;
+        }
+        assert_eq!(learning.threshold_for("code.exe", BASE), BASE);
+        let learned = learning.learned["code.exe"];
+        assert_eq!(learned.samples, 100);
+        assert!(learned.typical_gap_seconds >= BASE.as_secs_f64() / THRESHOLD_MARGIN);
+
+        // Long gaps still raise it from there
+        for _ in 0..50 {
+            learning
+                .record_gap("code.exe", Duration::from_secs(290), BASE)
+                .await;
+        }
+        assert!(learning.threshold_for("code.exe", BASE) > BASE);
+    }
+}
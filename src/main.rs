@@ -1,62 +1,213 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use chrono::Local;
+use chrono::{Local, NaiveDate};
 use dirs;
 use dotenvy::dotenv;
 use env_logger::Builder;
-use log::{error, info};
+use log::{debug, error, info};
 use rusqlite::Connection;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc, Mutex};
 use uuid::Uuid;
 
+mod activity;
+mod dashboard;
 mod db;
+mod disk_guard;
+mod games;
+mod idle;
+mod meetings;
+mod network;
 mod platform;
-
-use db::connection::upset_app_usage;
-use db::models::{App, AppUsage};
+mod process_watch;
+mod retry;
+mod sharing;
+mod title_parser;
+mod widget;
+
+use activity::ActivityRules;
+use db::connection::{
+    create_session, find_resumable_session, merge_app_data, record_machine_boot,
+    record_machine_shutdown, update_machine_last_seen, upset_app_usage, UPDATE_CHANNEL_CAPACITY,
+};
+use db::heartbeats::record_heartbeat;
+use db::models::{App, AppUsage, Sessions, IDLE_WINDOW_TITLE};
+use db::reports::DayBoundary;
+use disk_guard::DiskGuard;
+use idle::{IdleEvent, IdleLearning, IdleMonitor};
+use network::NetworkPolicy;
+use platform::scrub::ScrubRules;
 use platform::windows::{self, WindowsHandle};
-use platform::{Platform, WindowDetails};
+use platform::{Platform, PrivacyLevel, PrivacySettings, WindowDetails};
+use title_parser::TitleParser;
 
 // Types
 type AppMap = HashMap<String, App>;
 type UsageMap = HashMap<String, AppUsage>;
 type AppData = (AppMap, UsageMap);
-type Sender = mpsc::UnboundedSender<AppData>;
+type Sender = mpsc::Sender<AppData>;
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 // Constants
 const IDLE_THRESHOLD_SECS: u64 = 300;
+const DEFAULT_ADAPTIVE_IDLE_MAX_MINUTES: u64 = 20;
 const TRACKING_INTERVAL_MS: u64 = 1000;
+const DEFAULT_MAINTENANCE_HOUR: u32 = 3;
+const DEFAULT_SESSION_RESUME_MINUTES: i64 = 5;
+const MACHINE_LAST_SEEN_INTERVAL_SECS: u64 = 60;
+/// Shorter than a minute so every minute the tracker runs gets a heartbeat
+const HEARTBEAT_INTERVAL_SECS: u64 = 20;
+const DEFAULT_UNFOCUSED_WEIGHT: f64 = 1.0;
+const DEFAULT_MIN_FREE_DISK_MB: u64 = 500;
+const DEFAULT_DASHBOARD_RATE_LIMIT: u32 = 60;
+/// Window state key prefix of the synthetic idle entries
+const IDLE_KEY_PREFIX: &str = "Idle Time";
 
 /// Application configuration structure
 struct Config {
-    session_id: String,
     db_path: PathBuf,
     log_path: PathBuf,
+    title_rules_path: PathBuf,
+    activity_rules_path: PathBuf,
+    widget_path: PathBuf,
+    /// Local hour of the day the database maintenance runs in
+    maintenance_hour: u32,
+    /// A session from the same boot that ended within this window is resumed
+    session_resume_window: chrono::Duration,
+    /// How much of each window title is recorded
+    privacy: PrivacySettings,
+    /// Where reporting days and weeks start
+    day_boundary: DayBoundary,
+    /// Share of a second credited to visible windows that aren't focused. At 0
+    /// only focused time counts, though rows recorded before focus was tracked
+    /// count as focused
+    unfocused_weight: f64,
+    /// Address and token of the optional read-only dashboard
+    dashboard: Option<(SocketAddr, String)>,
+    /// API requests per minute allowed with the dashboard token
+    dashboard_rate_limit: u32,
+    /// Upper bound of the per-app learned idle threshold, when adaptive idle is on
+    adaptive_idle_max: Option<Duration>,
+    /// Free space on the database volume below which non-essential writes pause
+    min_free_disk_bytes: u64,
+    /// File aggregate stats are shared to, when sharing is on
+    shared_stats_path: Option<PathBuf>,
+    /// Record time in games found in launcher libraries as the Games activity
+    detect_games: bool,
+    /// Executables whose process lifetime is recorded even without a window
+    watched_processes: Vec<String>,
+    /// Whether network-using subsystems may currently use the network
+    network: NetworkPolicy,
 }
 
 impl Config {
     fn new() -> Result<Self> {
         let db_path = get_database_path()?;
-        let log_path = db_path
-            .parent()
-            .unwrap_or_else(|| Path::new("."))
-            .join("application.log");
+        let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+        let log_path = data_dir.join("application.log");
+        let title_rules_path = data_dir.join("title_rules.json");
+        let activity_rules_path = data_dir.join("activity_rules.json");
+        let widget_path = data_dir.join("widget.json");
+        let maintenance_hour = env_or("MAINTENANCE_HOUR", DEFAULT_MAINTENANCE_HOUR) % 24;
+        let session_resume_window = chrono::Duration::minutes(env_or(
+            "SESSION_RESUME_MINUTES",
+            DEFAULT_SESSION_RESUME_MINUTES,
+        ));
+        let unfocused_weight = env_or("UNFOCUSED_WEIGHT", DEFAULT_UNFOCUSED_WEIGHT).clamp(0.0, 1.0);
+        let dashboard = dashboard_settings();
+        let dashboard_rate_limit = env_or("DASHBOARD_RATE_LIMIT", DEFAULT_DASHBOARD_RATE_LIMIT);
+        let day_boundary = DayBoundary {
+            hour: env_or("DAY_BOUNDARY_HOUR", 0) % 24,
+            week_start: env_or("WEEK_START", chrono::Weekday::Mon),
+        };
+        let adaptive_idle_max = env_or("ADAPTIVE_IDLE", false).then(|| {
+            Duration::from_secs(
+                env_or(
+                    "ADAPTIVE_IDLE_MAX_MINUTES",
+                    DEFAULT_ADAPTIVE_IDLE_MAX_MINUTES,
+                ) * 60,
+            )
+        });
+        let min_free_disk_bytes =
+            env_or("MIN_FREE_DISK_MB", DEFAULT_MIN_FREE_DISK_MB) * 1024 * 1024;
+        let shared_stats_path = Some(env_or("SHARED_STATS_PATH", String::new()))
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        let detect_games = env_or("DETECT_GAMES", true);
+        let watched_processes =
+            process_watch::parse_watchlist(&env_or("WATCHED_PROCESSES", String::new()));
+        let network = NetworkPolicy {
+            offline: env_or("OFFLINE_MODE", false),
+            allow_metered: env_or("ALLOW_METERED_NETWORK", false),
+        };
+        let privacy = PrivacySettings {
+            level: env_or("PRIVACY_LEVEL", PrivacyLevel::Full),
+            app_only_while_sharing: env_or("APP_ONLY_WHILE_SHARING", false),
+            scrub_rules: ScrubRules::load(&data_dir.join("scrub_rules.json")),
+        };
 
         Ok(Config {
-            session_id: Uuid::new_v4().to_string(),
             db_path,
             log_path,
+            title_rules_path,
+            activity_rules_path,
+            widget_path,
+            maintenance_hour,
+            session_resume_window,
+            privacy,
+            day_boundary,
+            unfocused_weight,
+            dashboard,
+            dashboard_rate_limit,
+            adaptive_idle_max,
+            min_free_disk_bytes,
+            shared_stats_path,
+            detect_games,
+            watched_processes,
+            network,
         })
     }
 }
 
+/// The dashboard only starts when both an address and an access token are configured
+fn dashboard_settings() -> Option<(SocketAddr, String)> {
+    let addr = env_or("DASHBOARD_ADDR", String::new());
+    if addr.is_empty() {
+        return None;
+    }
+    let token = env_or("DASHBOARD_TOKEN", String::new());
+    if token.is_empty() {
+        error!("DASHBOARD_ADDR is set without DASHBOARD_TOKEN, the dashboard stays off.");
+        return None;
+    }
+    match addr.parse() {
+        Ok(addr) => Some((addr, token)),
+        Err(_) => {
+            error!("Invalid value {:?} for DASHBOARD_ADDR.", addr);
+            None
+        }
+    }
+}
+
+/// Read an optional setting from the environment, falling back to `default`
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            error!("Invalid value {:?} for {}, using the default.", value, key);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
 /// Logger configuration and initialization
 struct Logger;
 
@@ -96,23 +247,38 @@ impl Logger {
 /// Application state tracker
 struct AppTracker {
     session_id: String,
+    title_parser: TitleParser,
     previous_app_map: AppMap,
     previous_app_usage_map: UsageMap,
+    /// Keys changed since the last call to `take_changes`
+    dirty_apps: HashSet<String>,
+    dirty_usages: HashSet<String>,
+    /// Rows replaced since the last call to `take_changes`, written once more with their end time
+    closed_usages: Vec<AppUsage>,
+    /// Day each app's executable version was last read, so it is checked once a day
+    version_checked_on: HashMap<String, NaiveDate>,
 }
 
 impl AppTracker {
-    fn new(session_id: String) -> Self {
+    fn new(session_id: String, title_parser: TitleParser) -> Self {
         Self {
             session_id,
+            title_parser,
             previous_app_map: HashMap::new(),
             previous_app_usage_map: HashMap::new(),
+            dirty_apps: HashSet::new(),
+            dirty_usages: HashSet::new(),
+            closed_usages: Vec::new(),
+            version_checked_on: HashMap::new(),
         }
     }
 
     fn update(&mut self, window_state: &BTreeMap<String, WindowDetails>) {
         let current_time = Local::now().naive_utc();
+        let today = Local::now().date_naive();
+        let focus_changed_at = WindowsHandle::get_last_focus_change();
 
-        for (_, details) in window_state.iter() {
+        for (key, details) in window_state.iter() {
             let app_name = details
                 .app_name
                 .clone()
@@ -122,52 +288,134 @@ impl AppTracker {
                 .clone()
                 .unwrap_or_else(|| "Unknown Path".to_string());
 
-            self.update_app(&app_name, &app_path);
-            self.update_usage(&details.window_title, &app_name, current_time);
+            self.update_app(&app_name, &app_path, today);
+            self.update_usage(key, details, &app_name, current_time, focus_changed_at);
         }
 
         self.previous_app_usage_map
             .retain(|key, _| window_state.contains_key(key));
+        let usages = &self.previous_app_usage_map;
+        self.dirty_usages.retain(|key| usages.contains_key(key));
     }
 
-    fn update_app(&mut self, app_name: &str, app_path: &str) {
+    fn update_app(&mut self, app_name: &str, app_path: &str, today: NaiveDate) {
+        let previous = self.previous_app_map.get(app_name);
+        let unchanged = previous.is_some_and(|app| app.path == app_path);
+        if unchanged && self.version_checked_on.get(app_name) == Some(&today) {
+            return;
+        }
+
+        // Re-read the version daily so updates installed while tracking are noticed
+        let version = WindowsHandle::get_file_version(app_path);
+        if let Some((old, new)) = previous
+            .and_then(|app| app.version.as_ref())
+            .zip(version.as_ref())
+            .filter(|(old, new)| old != new)
+        {
+            info!("{} changed version from {} to {}", app_name, old, new);
+        }
+        self.version_checked_on.insert(app_name.to_string(), today);
+
         self.previous_app_map.insert(
             app_name.to_string(),
             App {
                 name: app_name.to_string(),
                 path: app_path.to_string(),
+                version,
             },
         );
+        self.dirty_apps.insert(app_name.to_string());
     }
 
     fn update_usage(
         &mut self,
-        window_title: &str,
+        key: &str,
+        details: &WindowDetails,
         app_name: &str,
         current_time: chrono::NaiveDateTime,
+        focus_changed_at: Option<chrono::NaiveDateTime>,
     ) {
-        match self.previous_app_usage_map.entry(window_title.to_string()) {
-            std::collections::hash_map::Entry::Occupied(mut entry) => {
-                entry.get_mut().last_updated_time = current_time;
+        let window_title = &details.window_title;
+        let mut start_time = current_time;
+        if let Some(usage) = self.previous_app_usage_map.get_mut(key) {
+            let same_origin = usage.remote_session == details.is_remote_session;
+            if same_origin && usage.focused == details.is_active {
+                let starts_playing = details.is_playing_audio && !usage.playing_audio;
+                if usage.last_updated_time != current_time || starts_playing {
+                    usage.last_updated_time = current_time;
+                    usage.playing_audio |= details.is_playing_audio;
+                    self.dirty_usages.insert(key.to_string());
+                }
+                return;
             }
-            std::collections::hash_map::Entry::Vacant(entry) => {
-                entry.insert(AppUsage {
-                    session_id: self.session_id.clone(),
-                    app_id: Uuid::new_v4().to_string(),
-                    application_name: app_name.to_string(),
-                    current_screen_title: window_title.to_string(),
-                    start_time: current_time,
-                    last_updated_time: current_time,
-                });
+
+            // Switching between remote and local use or gaining or losing focus starts a
+            // new row so each row has one state. Focus changes are split at the moment
+            // the foreground window changed rather than at this tick.
+            if same_origin {
+                if let Some(changed_at) = focus_changed_at.filter(|changed_at| {
+                    (usage.last_updated_time..=current_time).contains(changed_at)
+                }) {
+                    start_time = changed_at;
+                }
+            }
+            usage.last_updated_time = start_time;
+            self.closed_usages.push(usage.clone());
+        }
+
+        let parsed = self.title_parser.parse(app_name, window_title);
+        self.previous_app_usage_map.insert(
+            key.to_string(),
+            AppUsage {
+                session_id: self.session_id.clone(),
+                app_id: Uuid::new_v4().to_string(),
+                application_name: app_name.to_string(),
+                current_screen_title: window_title.clone(),
+                start_time,
+                last_updated_time: current_time,
+                project: parsed.project,
+                file: parsed.file,
+                repository: parsed.repository,
+                branch: parsed.branch,
+                playing_audio: details.is_playing_audio,
+                remote_session: details.is_remote_session,
+                focused: details.is_active,
+            },
+        );
+        self.dirty_usages.insert(key.to_string());
+    }
+
+    /// Close idle rows at the input that ended the idle period instead of the next tick
+    fn end_idle(&mut self, resumed_at: chrono::NaiveDateTime) {
+        for (key, usage) in self.previous_app_usage_map.iter_mut() {
+            if key.starts_with(IDLE_KEY_PREFIX) {
+                usage.last_updated_time = resumed_at.max(usage.start_time);
+                self.dirty_usages.insert(key.clone());
             }
         }
     }
 
-    fn get_state(&self) -> AppData {
-        (
-            self.previous_app_map.clone(),
-            self.previous_app_usage_map.clone(),
-        )
+    /// Rows changed since the previous call, so unchanged rows are neither cloned nor rewritten
+    fn take_changes(&mut self) -> AppData {
+        let apps = self
+            .dirty_apps
+            .drain()
+            .filter_map(|key| {
+                let app = self.previous_app_map.get(&key)?.clone();
+                Some((key, app))
+            })
+            .collect();
+        // Keyed by row id so a closed row and its replacement don't collide when coalesced
+        let mut usages: UsageMap = self
+            .closed_usages
+            .drain(..)
+            .map(|usage| (usage.app_id.clone(), usage))
+            .collect();
+        usages.extend(self.dirty_usages.drain().filter_map(|key| {
+            let usage = self.previous_app_usage_map.get(&key)?.clone();
+            Some((usage.app_id.clone(), usage))
+        }));
+        (apps, usages)
     }
 }
 
@@ -175,13 +423,21 @@ impl AppTracker {
 struct WindowStateManager;
 
 impl WindowStateManager {
-    fn get_current_state() -> BTreeMap<String, WindowDetails> {
-        let window_state = windows::WindowsHandle::get_window_titles();
-        let idle_time_secs = WindowsHandle::get_last_input_info()
-            .unwrap_or_default()
-            .as_secs();
+    fn get_current_state(
+        is_idle: bool,
+        privacy: &PrivacySettings,
+    ) -> BTreeMap<String, WindowDetails> {
+        // Nothing is in use while the workstation is locked, the display is off or
+        // another user owns the console, so every open row is closed
+        if WindowsHandle::is_session_locked()
+            || WindowsHandle::is_display_off()
+            || !WindowsHandle::is_session_active()
+        {
+            return BTreeMap::new();
+        }
+        let window_state = windows::WindowsHandle::get_window_titles(privacy);
 
-        if idle_time_secs >= IDLE_THRESHOLD_SECS {
+        if is_idle {
             Self::augment_with_idle_state(window_state)
         } else {
             window_state
@@ -194,7 +450,8 @@ impl WindowStateManager {
         if let Some(first_entry) = window_state.first_entry() {
             let value = first_entry.get().clone();
             let key = format!(
-                "Idle Time{}",
+                "{}{}",
+                IDLE_KEY_PREFIX,
                 value
                     .app_name
                     .clone()
@@ -203,10 +460,12 @@ impl WindowStateManager {
             window_state.insert(
                 key,
                 WindowDetails {
-                    window_title: "Idle".to_owned(),
+                    window_title: IDLE_WINDOW_TITLE.to_owned(),
                     app_name: value.app_name,
                     app_path: value.app_path,
                     is_active: false,
+                    is_playing_audio: false,
+                    is_remote_session: value.is_remote_session,
                 },
             );
         }
@@ -214,6 +473,75 @@ impl WindowStateManager {
     }
 }
 
+/// Boot time of the machine in UTC, derived from the tick count
+fn current_boot_time() -> chrono::NaiveDateTime {
+    let uptime = chrono::Duration::from_std(WindowsHandle::get_uptime()).unwrap_or_default();
+    Local::now().naive_utc() - uptime
+}
+
+/// Keep the machine session's last seen time current so the power-off time is known
+async fn track_machine_uptime(conn: Arc<Mutex<Connection>>, boot_time: chrono::NaiveDateTime) {
+    let mut interval = tokio::time::interval(Duration::from_secs(MACHINE_LAST_SEEN_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let now = Local::now().naive_utc();
+        if let Err(err) = update_machine_last_seen(&*conn.lock().await, boot_time, now) {
+            error!("Failed to update machine session: {}", err);
+        }
+    }
+}
+
+/// Record which minutes the tracker was running, so days with gaps can be flagged
+async fn record_heartbeats(conn: Arc<Mutex<Connection>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let now = Local::now().naive_utc();
+        if let Err(err) = record_heartbeat(&*conn.lock().await, now) {
+            error!("Failed to record heartbeat: {}", err);
+        }
+    }
+}
+
+/// Resume the previous session if it ended recently on the same boot, otherwise start a new one
+async fn start_session(
+    conn: &Arc<Mutex<Connection>>,
+    config: &Config,
+    boot_time: chrono::NaiveDateTime,
+) -> String {
+    let now = Local::now().naive_utc();
+    let user_name = WindowsHandle::get_user_name().unwrap_or_else(|| "Unknown User".to_string());
+    let conn = conn.lock().await;
+
+    match find_resumable_session(
+        &conn,
+        &user_name,
+        boot_time,
+        now - config.session_resume_window,
+    ) {
+        Ok(Some(session_id)) => {
+            info!("Resuming session {}", session_id);
+            return session_id;
+        }
+        Ok(None) => {}
+        Err(err) => error!("Failed to look up a resumable session: {}", err),
+    }
+
+    let session = Sessions {
+        id: Uuid::new_v4().to_string(),
+        session_date: Local::now().date_naive(),
+        user_name,
+        start_time: now,
+        end_time: now,
+        boot_time,
+    };
+    if let Err(err) = create_session(&conn, &session) {
+        error!("Failed to record session {}: {}", session.id, err);
+    }
+    info!("Started session {}", session.id);
+    session.id
+}
+
 /// Database path resolution
 fn get_database_path() -> Result<PathBuf> {
     let db_url = std::env::var("DATABASE_URL")
@@ -226,32 +554,75 @@ fn get_database_path() -> Result<PathBuf> {
     })
 }
 
+/// Fold a pending snapshot and a newer one into a single snapshot
+fn coalesce(pending: Option<AppData>, data: AppData) -> AppData {
+    match pending {
+        Some(mut older) => {
+            merge_app_data(&mut older, data);
+            older
+        }
+        None => data,
+    }
+}
+
+/// Send a snapshot without blocking the tracker. When the database writer is
+/// backed up the snapshot is kept as pending and merged with the next one.
+fn send_or_coalesce(tx: &Sender, pending: &mut Option<AppData>, data: AppData) {
+    let data = coalesce(pending.take(), data);
+    if data.0.is_empty() && data.1.is_empty() {
+        return;
+    }
+    match tx.try_send(data) {
+        Ok(()) => {}
+        Err(TrySendError::Full(data)) => {
+            debug!("Database writer is busy, coalescing update.");
+            *pending = Some(data);
+        }
+        Err(TrySendError::Closed(_)) => {
+            error!("Database writer has stopped, dropping update.");
+        }
+    }
+}
+
 /// Main tracking loop
 async fn track_application_usage(
     session_id: String,
+    title_parser: TitleParser,
+    privacy: PrivacySettings,
     tx: Sender,
     mut ctrl_c_recv: mpsc::UnboundedReceiver<()>,
+    mut idle_recv: broadcast::Receiver<IdleEvent>,
 ) {
-    let mut tracker = AppTracker::new(session_id);
+    let mut tracker = AppTracker::new(session_id, title_parser);
     let mut previous_state = None;
+    let mut pending = None;
+    let mut is_idle = false;
     loop {
         tokio::select! {
             Some(_) = ctrl_c_recv.recv() => {
                 info!("Shutdown signal received.");
-                if let Err(err) = tx.send(tracker.get_state()) {
+                let data = coalesce(pending.take(), tracker.take_changes());
+                if let Err(err) = tx.send(data).await {
                     error!("Error sending data on shutdown: {:?}", err);
                 }
                 break;
             }
+            Ok(event) = idle_recv.recv() => {
+                is_idle = matches!(event, IdleEvent::Started { .. });
+                if let IdleEvent::Ended { resumed_at, .. } = event {
+                    tracker.end_idle(resumed_at);
+                    send_or_coalesce(&tx, &mut pending, tracker.take_changes());
+                }
+            }
             _ = async {
                 let start = Instant::now();
-                let window_state = WindowStateManager::get_current_state();
+                let window_state = WindowStateManager::get_current_state(is_idle, &privacy);
                 if previous_state.as_ref() != Some(&window_state) {
                     previous_state = Some(window_state.clone());
                     tracker.update(&window_state);
-                    if let Err(err) = tx.send(tracker.get_state()) {
-                        error!("Error sending updated data: {:?}", err);
-                    }
+                    send_or_coalesce(&tx, &mut pending, tracker.take_changes());
+                } else if let Some(data) = pending.take() {
+                    send_or_coalesce(&tx, &mut pending, data);
                 }
                 let sleep_duration = TRACKING_INTERVAL_MS.saturating_sub(start.elapsed().as_millis() as u64);
                 tokio::time::sleep(Duration::from_millis(sleep_duration)).await;
@@ -282,20 +653,103 @@ async fn main() -> Result<()> {
     ));
     info!("Database connected at {:?}", config.db_path);
 
+    let boot_time = {
+        let detected = current_boot_time();
+        let now = Local::now().naive_utc();
+        record_machine_boot(&*conn.lock().await, detected, now).unwrap_or_else(|err| {
+            error!("Failed to record machine boot: {}", err);
+            detected
+        })
+    };
+    let session_id = start_session(&conn, &config, boot_time).await;
+
     let (ctrl_c_tx, ctrl_c_rx) = mpsc::unbounded_channel();
-    let (tx, rx) = mpsc::unbounded_channel();
+    let (tx, rx) = mpsc::channel(UPDATE_CHANNEL_CAPACITY);
+
+    let mut idle_monitor = IdleMonitor::new(
+        Duration::from_secs(IDLE_THRESHOLD_SECS),
+        Duration::from_millis(TRACKING_INTERVAL_MS),
+    );
+    if let Some(max_threshold) = config.adaptive_idle_max {
+        idle_monitor =
+            idle_monitor.with_learning(IdleLearning::load(conn.clone(), max_threshold).await);
+    }
+    let idle_rx = idle_monitor.subscribe();
+    tokio::spawn(idle_monitor.run());
 
     let signal_task = tokio::spawn(async move {
         tokio::signal::ctrl_c().await.unwrap();
         let _ = ctrl_c_tx.send(());
     });
 
+    let disk_guard = DiskGuard::default();
+    tokio::spawn(
+        disk_guard
+            .clone()
+            .monitor(config.db_path.clone(), config.min_free_disk_bytes),
+    );
+    tokio::spawn(process_watch::track_watched_processes(
+        conn.clone(),
+        session_id.clone(),
+        config.watched_processes.clone(),
+        disk_guard.clone(),
+    ));
     let tracking_task = tokio::spawn(track_application_usage(
-        config.session_id.clone(),
+        session_id,
+        TitleParser::load(&config.title_rules_path),
+        config.privacy,
         tx,
         ctrl_c_rx,
+        idle_rx,
+    ));
+    tokio::spawn(widget::refresh_widget_file(
+        conn.clone(),
+        config.widget_path.clone(),
+        config.unfocused_weight,
+        config.day_boundary,
+        disk_guard.clone(),
+    ));
+    tokio::spawn(track_machine_uptime(conn.clone(), boot_time));
+    tokio::spawn(record_heartbeats(conn.clone()));
+    tokio::spawn(meetings::record_meetings(
+        conn.clone(),
+        config.day_boundary,
+        disk_guard.clone(),
     ));
-    let db_task = tokio::spawn(upset_app_usage(conn, rx));
+    if let Some((addr, token)) = config.dashboard.clone() {
+        tokio::spawn(dashboard::serve_dashboard(
+            conn.clone(),
+            addr,
+            token,
+            config.dashboard_rate_limit,
+            config.unfocused_weight,
+            config.day_boundary,
+            config.network,
+        ));
+    }
+    let activity_rules = ActivityRules::load(&config.activity_rules_path);
+    if let Some(path) = config.shared_stats_path.clone() {
+        tokio::spawn(sharing::export_shared_stats(
+            conn.clone(),
+            path,
+            config.day_boundary,
+            activity_rules.literal_activities(),
+            disk_guard.clone(),
+        ));
+    }
+    tokio::spawn(activity::summarize_activities(
+        conn.clone(),
+        activity_rules,
+        config.detect_games,
+        config.day_boundary,
+        disk_guard.clone(),
+    ));
+    tokio::spawn(db::maintenance::schedule_maintenance(
+        conn.clone(),
+        config.maintenance_hour,
+        disk_guard,
+    ));
+    let db_task = tokio::spawn(upset_app_usage(conn.clone(), rx));
 
     let (tracking_res, db_res, _) = tokio::join!(tracking_task, db_task, signal_task);
 
@@ -306,5 +760,10 @@ async fn main() -> Result<()> {
         error!("Database task failed: {:?}", err);
     }
 
+    let now = Local::now().naive_utc();
+    if let Err(err) = record_machine_shutdown(&*conn.lock().await, boot_time, now) {
+        error!("Failed to record shutdown: {}", err);
+    }
+
     Ok(())
 }
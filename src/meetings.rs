@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use log::{debug, error};
+use regex::Regex;
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+
+use crate::db::activities::{usages_updated_since, UsageSpan};
+use crate::db::meetings::record_meeting;
+use crate::db::reports::DayBoundary;
+use crate::disk_guard::DiskGuard;
+
+const MEETING_SCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Meeting rows closer together than this are joined into one meeting
+const MEETING_GAP_MINUTES: i64 = 2;
+
+/// Executables and the titles their windows show while a meeting is in progress
+const MEETING_TITLE_PATTERNS: &[(&[&str], &str)] = &[
+    (&["zoom.exe"], r"(?i)^zoom (meeting|webinar)"),
+    (&["ms-teams.exe", "teams.exe"], r"(?i)\b(meeting|call)\b"),
+    (&["ciscocollabhost.exe", "webex.exe"], r"(?i)\bmeeting\b"),
+    (
+        &["chrome.exe", "msedge.exe", "firefox.exe", "brave.exe"],
+        r"^Meet - [a-z]{3}-[a-z]{4}-[a-z]{3}\b",
+    ),
+];
+
+/// Recognizes usage rows that belong to an online meeting. A window only
+/// counts while its app also holds an audio session, which keeps calendar
+/// and chat windows that mention a meeting out.
+struct MeetingDetector {
+    rules: Vec<(&'static [&'static str], Regex)>,
+}
+
+impl MeetingDetector {
+    fn new() -> Self {
+        let rules = MEETING_TITLE_PATTERNS
+            .iter()
+            .filter_map(|(apps, pattern)| match Regex::new(pattern) {
+                Ok(pattern) => Some((*apps, pattern)),
+                Err(err) => {
+                    error!("Invalid meeting title pattern {:?}: {}", pattern, err);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    fn is_meeting(&self, usage: &UsageSpan) -> bool {
+        usage.playing_audio
+            && self.rules.iter().any(|(apps, pattern)| {
+                apps.iter()
+                    .any(|app| app.eq_ignore_ascii_case(&usage.application_name))
+                    && pattern.is_match(&usage.title)
+            })
+    }
+}
+
+/// Periodically record meeting blocks from conferencing app usage rows
+pub async fn record_meetings(
+    conn: Arc<Mutex<Connection>>,
+    boundary: DayBoundary,
+    disk_guard: DiskGuard,
+) {
+    let detector = MeetingDetector::new();
+    let gap = chrono::Duration::minutes(MEETING_GAP_MINUTES);
+    // Rows are re-read while they keep growing, which only widens existing meetings
+    let mut since: NaiveDateTime = boundary.start_of_today();
+    let mut interval = tokio::time::interval(MEETING_SCAN_INTERVAL);
+    loop {
+        interval.tick().await;
+        // Rows since the last pass are picked up once space frees up
+        if disk_guard.is_low_on_space() {
+            continue;
+        }
+
+        let conn = conn.lock().await;
+        let usages = match usages_updated_since(&conn, since) {
+            Ok(usages) => usages,
+            Err(err) => {
+                error!("Failed to query usages for meetings: {}", err);
+                continue;
+            }
+        };
+
+        for usage in usages.iter().filter(|usage| detector.is_meeting(usage)) {
+            if let Err(err) = record_meeting(&conn, usage, gap) {
+                error!(
+                    "Failed to record meeting in {}: {}",
+                    usage.application_name, err
+                );
+            }
+        }
+        if let Some(latest) = usages.iter().map(|usage| usage.end_time).max() {
+            since = latest;
+        }
+        debug!("Scanned {} usage rows for meetings", usages.len());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+
+/// Central check every subsystem that uses the network must consult first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetworkPolicy {
+    /// Keep all network use off regardless of the connection
+    pub offline: bool,
+    /// Also allow network use while the connection is metered
+    pub allow_metered: bool,
+}
+
+impl NetworkPolicy {
+    /// Reason network use is currently not allowed, if any
+    pub fn blocked_reason(&self) -> Option<&'static str> {
+        if self.offline {
+            Some("offline mode is on")
+        } else if !self.allow_metered && WindowsHandle::is_metered_connection() {
+            Some("the connection is metered")
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use log::{debug, error};
+use windows::Win32::{
+    Foundation::{LPARAM, WPARAM},
+    System::{
+        Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED},
+        Threading::GetCurrentThreadId,
+    },
+    UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, PeekMessageW, PostThreadMessageW, TranslateMessage, MSG,
+        PM_NOREMOVE, WM_APP,
+    },
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Posted to the COM thread when jobs are waiting in its channel
+const WM_RUN_JOBS: u32 = WM_APP + 1;
+
+struct ComThread {
+    jobs: Mutex<Sender<Job>>,
+    thread_id: Option<u32>,
+}
+
+static COM_THREAD: OnceLock<ComThread> = OnceLock::new();
+
+/// Start the thread that owns the single-threaded COM apartment.
+/// The thread pumps window messages while it waits, as an STA has to.
+fn spawn_com_thread() -> ComThread {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("com-sta".to_string())
+        .spawn(move || {
+            let initialized = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+            if initialized.is_err() {
+                error!(
+                    "Failed to initialize COM on the STA thread: {:?}",
+                    initialized
+                );
+                return;
+            }
+            // Create the message queue before anyone posts to it
+            let mut msg = MSG::default();
+            let _ = unsafe { PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE) };
+            let _ = ready_tx.send(unsafe { GetCurrentThreadId() });
+
+            // Runs until WM_QUIT or an error, which in practice is process exit
+            while unsafe { GetMessageW(&mut msg, None, 0, 0) }.0 > 0 {
+                if msg.message == WM_RUN_JOBS {
+                    while let Ok(job) = rx.try_recv() {
+                        job();
+                    }
+                } else {
+                    unsafe {
+                        let _ = TranslateMessage(&msg);
+                        DispatchMessageW(&msg);
+                    }
+                }
+            }
+            unsafe { CoUninitialize() };
+        });
+    if let Err(err) = spawned {
+        error!("Failed to spawn the COM thread: {:?}", err);
+    }
+    ComThread {
+        jobs: Mutex::new(tx),
+        // Dropped without a value when the thread failed to start
+        thread_id: ready_rx.recv().ok(),
+    }
+}
+
+/// Run `job` on the dedicated COM thread and wait for its result.
+/// COM objects must be created and released inside the job.
+pub fn run_on_com_thread<T, F>(job: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (result_tx, result_rx) = mpsc::channel();
+    let job: Job = Box::new(move || {
+        let _ = result_tx.send(job());
+    });
+
+    let com_thread = COM_THREAD.get_or_init(spawn_com_thread);
+    let Some(thread_id) = com_thread.thread_id else {
+        // The reason was logged once when the thread failed to start
+        debug!("The COM thread is not running.");
+        return None;
+    };
+    com_thread.jobs.lock().ok()?.send(job).ok()?;
+    if let Err(err) = unsafe { PostThreadMessageW(thread_id, WM_RUN_JOBS, WPARAM(0), LPARAM(0)) } {
+        debug!("Failed to wake the COM thread: {:?}", err);
+        return None;
+    }
+    result_rx.recv().ok()
+}
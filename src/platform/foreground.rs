@@ -0,0 +1,68 @@
+use std::sync::{Mutex, Once};
+use std::thread;
+
+use chrono::{Local, NaiveDateTime};
+use log::{debug, error};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    DispatchMessageW, GetMessageW, EVENT_SYSTEM_FOREGROUND, MSG, WINEVENT_OUTOFCONTEXT,
+};
+
+static LAST_FOREGROUND_CHANGE: Mutex<Option<NaiveDateTime>> = Mutex::new(None);
+static START_LISTENER: Once = Once::new();
+
+/// UTC time the foreground window last changed, as reported by the system
+/// instead of the next tracker tick. The first call starts the event hook.
+pub fn last_foreground_change() -> Option<NaiveDateTime> {
+    START_LISTENER.call_once(spawn_foreground_listener);
+    LAST_FOREGROUND_CHANGE.lock().ok().and_then(|time| *time)
+}
+
+fn spawn_foreground_listener() {
+    let spawned = thread::Builder::new()
+        .name("foreground-events".to_string())
+        .spawn(|| unsafe { run_foreground_listener() });
+    if let Err(err) = spawned {
+        error!("Failed to spawn the foreground event thread: {:?}", err);
+    }
+}
+
+/// Out-of-context hooks are delivered through the message queue of the thread that set them
+unsafe fn run_foreground_listener() {
+    let hook = SetWinEventHook(
+        EVENT_SYSTEM_FOREGROUND,
+        EVENT_SYSTEM_FOREGROUND,
+        None,
+        Some(on_foreground_change),
+        0,
+        0,
+        WINEVENT_OUTOFCONTEXT,
+    );
+    if hook.is_invalid() {
+        error!("Failed to hook foreground window changes.");
+        return;
+    }
+
+    let mut message = MSG::default();
+    while GetMessageW(&mut message, None, 0, 0).as_bool() {
+        DispatchMessageW(&message);
+    }
+    let _ = UnhookWinEvent(hook);
+}
+
+unsafe extern "system" fn on_foreground_change(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    window: HWND,
+    _object_id: i32,
+    _child_id: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    let now = Local::now().naive_utc();
+    if let Ok(mut last_change) = LAST_FOREGROUND_CHANGE.lock() {
+        *last_change = Some(now);
+    }
+    debug!("Foreground window changed to {:?}", window);
+}
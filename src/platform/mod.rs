@@ -1,5 +1,14 @@
-use std::{collections::BTreeMap, time::Duration};
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
 
+use scrub::ScrubRules;
+
+#[cfg(windows)]
+mod com_thread;
+#[cfg(windows)]
+mod foreground;
+#[cfg(windows)]
+mod power;
+pub mod scrub;
 #[cfg(windows)]
 pub mod windows;
 
@@ -8,10 +17,314 @@ pub struct WindowDetails {
     pub window_title: String,
     pub app_name: Option<String>,
     pub app_path: Option<String>,
+    /// The window is the foreground window
     pub is_active: bool,
+    /// The window's executable owns an active audio session
+    pub is_playing_audio: bool,
+    /// The desktop is being used over Remote Desktop
+    pub is_remote_session: bool,
+}
+
+/// A running process as listed by the process snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub process_id: u32,
+    pub parent_id: u32,
+    pub exe_name: String,
+}
+
+/// How much of a window title is kept before it leaves the platform layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrivacyLevel {
+    /// Keep the full window title
+    #[default]
+    Full,
+    /// Replace every title with the app name so only per-app time is stored
+    AppOnly,
+}
+
+impl FromStr for PrivacyLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "app_only" | "app-only" => Ok(Self::AppOnly),
+            _ => Err(format!("Unknown privacy level {:?}", value)),
+        }
+    }
+}
+
+/// Titles of the toolbars conferencing apps show while the screen is shared
+const SCREEN_SHARE_TITLE_MARKERS: &[&str] = &[
+    "sharing control bar",
+    "zoom share toolbar window",
+    "zoom share statusbar window",
+    "is sharing your screen",
+    "is sharing a window",
+];
+
+/// Title recorded for private browsing windows in place of the page title
+pub const PRIVATE_BROWSING_TITLE: &str = "Private browsing";
+
+/// Lowercase title fragments each browser puts in the titles of its private windows
+const PRIVATE_BROWSING_TITLE_MARKERS: &[(&str, &[&str])] = &[
+    ("chrome.exe", &["(incognito)", "new incognito tab"]),
+    ("msedge.exe", &["[inprivate]", "inprivate"]),
+    ("firefox.exe", &["private browsing"]),
+    ("brave.exe", &["(private)", "private window"]),
+    ("opera.exe", &["(private)"]),
+    ("vivaldi.exe", &["(private)", "private window"]),
+];
+
+/// Privacy options applied to window titles inside the platform layer
+#[derive(Debug, Default)]
+pub struct PrivacySettings {
+    pub level: PrivacyLevel,
+    /// Drop to app-only titles while a screen share toolbar is visible
+    pub app_only_while_sharing: bool,
+    /// Replacements applied to full titles
+    pub scrub_rules: ScrubRules,
+}
+
+impl PrivacySettings {
+    /// Level to apply to this snapshot of windows
+    fn effective_level(&self, state: &BTreeMap<String, WindowDetails>) -> PrivacyLevel {
+        if self.app_only_while_sharing && is_screen_sharing(state) {
+            PrivacyLevel::AppOnly
+        } else {
+            self.level
+        }
+    }
+
+    /// Strip or scrub titles, merging windows of an app that end up with the same title
+    pub fn apply(&self, state: BTreeMap<String, WindowDetails>) -> BTreeMap<String, WindowDetails> {
+        let level = self.effective_level(&state);
+        if level == PrivacyLevel::Full
+            && self.scrub_rules.is_empty()
+            && !state.values().any(is_private_browsing)
+        {
+            return state;
+        }
+
+        let mut redacted: BTreeMap<String, WindowDetails> = BTreeMap::new();
+        for (_, mut details) in state {
+            let title = match level {
+                // Private sessions are never stored verbatim, whatever the level
+                PrivacyLevel::Full if is_private_browsing(&details) => {
+                    PRIVATE_BROWSING_TITLE.to_string()
+                }
+                PrivacyLevel::Full => self.scrub_rules.scrub(&details.window_title),
+                PrivacyLevel::AppOnly => details
+                    .app_name
+                    .clone()
+                    .unwrap_or_else(|| "Unknown App".to_string()),
+            };
+            let key = window_key(details.app_name.as_deref(), &title);
+            details.window_title = title;
+            redacted
+                .entry(key)
+                .and_modify(|existing| {
+                    existing.is_active |= details.is_active;
+                    existing.is_playing_audio |= details.is_playing_audio;
+                })
+                .or_insert(details);
+        }
+        redacted
+    }
+}
+
+/// Key of a window in a snapshot. Different apps can show the same title, and
+/// the key is the same whatever privacy options apply, since the tracker
+/// keeps a usage row open for as long as its key stays in the snapshot.
+pub fn window_key(app_name: Option<&str>, title: &str) -> String {
+    format!("{}\u{0}{}", app_name.unwrap_or_default(), title)
+}
+
+/// Whether the window is a browser's private browsing window, judged by its title
+fn is_private_browsing(details: &WindowDetails) -> bool {
+    let Some(app_name) = &details.app_name else {
+        return false;
+    };
+    let title = details.window_title.to_lowercase();
+    PRIVATE_BROWSING_TITLE_MARKERS
+        .iter()
+        .filter(|(browser, _)| app_name.eq_ignore_ascii_case(browser))
+        .any(|(_, markers)| markers.iter().any(|marker| title.contains(marker)))
+}
+
+/// Whether a conferencing app is showing its screen share toolbar
+fn is_screen_sharing(state: &BTreeMap<String, WindowDetails>) -> bool {
+    state.values().any(|details| {
+        let title = details.window_title.to_lowercase();
+        SCREEN_SHARE_TITLE_MARKERS
+            .iter()
+            .any(|marker| title.contains(marker))
+    })
 }
 
 pub trait Platform {
-    fn get_window_titles() -> BTreeMap<String, WindowDetails>;
+    fn get_window_titles(privacy: &PrivacySettings) -> BTreeMap<String, WindowDetails>;
     fn get_last_input_info() -> Result<Duration, ()>;
+    fn get_uptime() -> Duration;
+    fn is_session_locked() -> bool;
+    fn is_session_active() -> bool;
+    fn is_display_off() -> bool;
+    fn is_metered_connection() -> bool;
+    fn get_last_focus_change() -> Option<chrono::NaiveDateTime>;
+    fn get_foreground_app() -> Option<String>;
+    fn get_running_processes() -> Vec<ProcessInfo>;
+    fn get_user_name() -> Option<String>;
+    fn get_file_version(path: &str) -> Option<String>;
+    fn get_free_disk_space(path: &std::path::Path) -> Option<u64>;
+    fn show_alert(title: &str, message: &str);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(windows: &[(&str, &str, bool)]) -> BTreeMap<String, WindowDetails> {
+        windows
+            .iter()
+            .map(|(app, title, is_active)| {
+                let details = WindowDetails {
+                    window_title: title.to_string(),
+                    app_name: Some(app.to_string()),
+                    app_path: None,
+                    is_active: *is_active,
+                    is_playing_audio: false,
+                    is_remote_session: false,
+                };
+                (window_key(Some(app), title), details)
+            })
+            .collect()
+    }
+
+    fn titles(state: &BTreeMap<String, WindowDetails>) -> Vec<(&str, &str, bool)> {
+        state
+            .values()
+            .map(|details| {
+                let app = details.app_name.as_deref().unwrap_or_default();
+                (app, details.window_title.as_str(), details.is_active)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn app_only_keeps_one_entry_per_app() {
+        let privacy = PrivacySettings {
+            level: PrivacyLevel::AppOnly,
+            ..Default::default()
+        };
+        let state = windows(&[
+            ("code.exe", "main.rs - app", false),
+            ("code.exe", "lib.rs - lib", false),
+            ("wt.exe", "PowerShell", true),
+        ]);
+        let applied = privacy.apply(state);
+        let mut applied = titles(&applied);
+        applied.sort();
+        assert_eq!(
+            applied,
+            [("code.exe", "code.exe", false), ("wt.exe", "wt.exe", true)]
+        );
+    }
+
+    #[test]
+    fn screen_share_switches_to_app_only() {
+        let privacy = PrivacySettings {
+            app_only_while_sharing: true,
+            ..Default::default()
+        };
+        let state = windows(&[
+            ("zoom.exe", "Zoom Share Toolbar Window", false),
+            ("code.exe", "secret.rs - app", true),
+        ]);
+        let applied = privacy.apply(state);
+        assert!(applied
+            .values()
+            .all(|details| { details.app_name.as_deref() == Some(details.window_title.as_str()) }));
+    }
+
+    #[test]
+    fn private_windows_merge_per_browser() {
+        let state = windows(&[
+            ("chrome.exe", "Bank - Google Chrome (Incognito)", false),
+            ("chrome.exe", "Mail - Google Chrome (Incognito)", true),
+            (
+                "firefox.exe",
+                "Search — Mozilla Firefox Private Browsing",
+                false,
+            ),
+            ("firefox.exe", "Docs — Mozilla Firefox", false),
+        ]);
+        let mut applied = titles(&PrivacySettings::default().apply(state))
+            .into_iter()
+            .map(|(app, title, is_active)| (app.to_string(), title.to_string(), is_active))
+            .collect::<Vec<_>>();
+        applied.sort();
+        let expected = [
+            ("chrome.exe", PRIVATE_BROWSING_TITLE, true),
+            ("firefox.exe", "Docs — Mozilla Firefox", false),
+            ("firefox.exe", PRIVATE_BROWSING_TITLE, false),
+        ]
+        .map(|(app, title, is_active)| (app.to_string(), title.to_string(), is_active));
+        assert_eq!(applied, expected);
+    }
+
+    #[test]
+    fn windows_keep_their_key_when_a_private_window_opens() {
+        let privacy = PrivacySettings::default();
+        let docs = ("firefox.exe", "Docs — Mozilla Firefox", true);
+        let before = privacy.apply(windows(&[docs]));
+        let after = privacy.apply(windows(&[
+            docs,
+            (
+                "firefox.exe",
+                "Search — Mozilla Firefox Private Browsing",
+                false,
+            ),
+        ]));
+        let key = window_key(Some("firefox.exe"), "Docs — Mozilla Firefox");
+        assert!(before.contains_key(&key));
+        assert_eq!(after.get(&key), before.get(&key));
+    }
+
+    #[test]
+    fn scrubbed_titles_merge_per_app() {
+        let privacy = PrivacySettings {
+            scrub_rules: ScrubRules::from_json(
+                r#"[{"pattern": "[\\w.]+@[\\w.]+", "replacement": "<email>"}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let state = windows(&[
+            ("outlook.exe", "Inbox - a@example.com", false),
+            ("outlook.exe", "Inbox - b@example.com", true),
+            ("thunderbird.exe", "Inbox - c@example.com", false),
+        ]);
+        let applied = privacy.apply(state);
+        let mut applied = titles(&applied);
+        applied.sort();
+        assert_eq!(
+            applied,
+            [
+                ("outlook.exe", "Inbox - <email>", true),
+                ("thunderbird.exe", "Inbox - <email>", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn unscrubbed_windows_keep_their_key_with_scrub_rules() {
+        let scrubbing = PrivacySettings {
+            scrub_rules: ScrubRules::from_json(r#"[{"pattern": "\\d{4}-\\d{4}"}]"#).unwrap(),
+            ..Default::default()
+        };
+        let state = windows(&[("code.exe", "main.rs - app", true)]);
+        let plain = PrivacySettings::default().apply(state.clone());
+        assert_eq!(scrubbing.apply(state), plain);
+    }
 }
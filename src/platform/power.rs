@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::thread;
+
+use log::{debug, error};
+use windows::core::w;
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Power::{
+    RegisterPowerSettingNotification, UnregisterPowerSettingNotification, POWERBROADCAST_SETTING,
+};
+use windows::Win32::System::SystemServices::GUID_CONSOLE_DISPLAY_STATE;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW,
+    DEVICE_NOTIFY_WINDOW_HANDLE, HMENU, HWND_MESSAGE, MSG, PBT_POWERSETTINGCHANGE, WINDOW_EX_STYLE,
+    WINDOW_STYLE, WM_POWERBROADCAST, WNDCLASSW,
+};
+
+/// `GUID_CONSOLE_DISPLAY_STATE` value reported when the display is powered down
+const DISPLAY_STATE_OFF: u32 = 0;
+
+static DISPLAY_OFF: AtomicBool = AtomicBool::new(false);
+static START_LISTENER: Once = Once::new();
+
+/// Whether the console display is currently powered down. The first call
+/// starts the thread that listens for display power notifications.
+pub fn is_display_off() -> bool {
+    START_LISTENER.call_once(spawn_power_listener);
+    DISPLAY_OFF.load(Ordering::Relaxed)
+}
+
+fn spawn_power_listener() {
+    let spawned = thread::Builder::new()
+        .name("power-notifications".to_string())
+        .spawn(|| unsafe { run_power_listener() });
+    if let Err(err) = spawned {
+        error!("Failed to spawn the power notification thread: {:?}", err);
+    }
+}
+
+/// Create a message-only window registered for display state changes and pump its messages
+unsafe fn run_power_listener() {
+    let instance = match GetModuleHandleW(None) {
+        Ok(instance) => instance,
+        Err(err) => {
+            error!("Failed to get the module handle: {:?}", err);
+            return;
+        }
+    };
+    let class_name = w!("AppWindowTrackerPowerListener");
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(power_window_proc),
+        hInstance: instance.into(),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        error!("Failed to register the power notification window class.");
+        return;
+    }
+    let window = match CreateWindowExW(
+        WINDOW_EX_STYLE(0),
+        class_name,
+        w!(""),
+        WINDOW_STYLE(0),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        HMENU::default(),
+        instance,
+        None,
+    ) {
+        Ok(window) => window,
+        Err(err) => {
+            error!("Failed to create the power notification window: {:?}", err);
+            return;
+        }
+    };
+    // The current display state is delivered right after registering
+    let notification = match RegisterPowerSettingNotification(
+        HANDLE(window.0),
+        &GUID_CONSOLE_DISPLAY_STATE,
+        DEVICE_NOTIFY_WINDOW_HANDLE,
+    ) {
+        Ok(notification) => notification,
+        Err(err) => {
+            error!("Failed to register for display notifications: {:?}", err);
+            return;
+        }
+    };
+
+    let mut message = MSG::default();
+    while GetMessageW(&mut message, None, 0, 0).as_bool() {
+        DispatchMessageW(&message);
+    }
+    let _ = UnregisterPowerSettingNotification(notification);
+}
+
+unsafe extern "system" fn power_window_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_POWERBROADCAST && wparam.0 as u32 == PBT_POWERSETTINGCHANGE {
+        let setting = &*(lparam.0 as *const POWERBROADCAST_SETTING);
+        if setting.PowerSetting == GUID_CONSOLE_DISPLAY_STATE
+            && setting.DataLength as usize >= std::mem::size_of::<u32>()
+        {
+            let state = std::ptr::read_unaligned(setting.Data.as_ptr() as *const u32);
+            let display_off = state == DISPLAY_STATE_OFF;
+            if DISPLAY_OFF.swap(display_off, Ordering::Relaxed) != display_off {
+                debug!("Display turned {}", if display_off { "off" } else { "on" });
+            }
+        }
+        return LRESULT(1);
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
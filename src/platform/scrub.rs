@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anyhow::Result;
+use log::{error, info};
+use regex::Regex;
+use serde::Deserialize;
+
+/// A rule as written in the scrub rules file
+#[derive(Debug, Deserialize)]
+struct ScrubRuleConfig {
+    pattern: String,
+    #[serde(default)]
+    replacement: String,
+}
+
+#[derive(Debug)]
+struct ScrubRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+/// User-defined regex replacements that remove sensitive fragments such as
+/// email subjects or account numbers from window titles before they are stored
+#[derive(Debug, Default)]
+pub struct ScrubRules {
+    rules: Vec<ScrubRule>,
+}
+
+impl ScrubRules {
+    /// Load rules from a JSON file, a missing file means no scrubbing
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read_rules(path) {
+            Ok(rules) => {
+                info!("Loaded {} scrub rules from {:?}", rules.rules.len(), path);
+                rules
+            }
+            Err(err) => {
+                error!("Failed to load scrub rules from {:?}: {:?}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    fn read_rules(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse rules in the format of the rules file
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let configs: Vec<ScrubRuleConfig> = serde_json::from_str(contents)?;
+        let rules = configs
+            .into_iter()
+            .map(|config| {
+                Ok(ScrubRule {
+                    pattern: Regex::new(&config.pattern)?,
+                    replacement: config.replacement,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply every rule in order, each to the output of the previous one
+    pub fn scrub(&self, title: &str) -> String {
+        self.rules.iter().fold(title.to_string(), |title, rule| {
+            rule.pattern
+                .replace_all(&title, rule.replacement.as_str())
+                .into_owned()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_apply_in_order() {
+        let rules = ScrubRules::from_json(
+            r#"[
+                {"pattern": "\\d{4}-\\d{4}", "replacement": "<card>"},
+                {"pattern": "<card> ending", "replacement": "card ending"},
+                {"pattern": "Subject: .*"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.scrub("Pay 1234-5678 ending - Bank"),
+            "Pay card ending - Bank"
+        );
+        assert_eq!(rules.scrub("Mail - Subject: raise"), "Mail - ");
+        assert_eq!(ScrubRules::default().scrub("Unchanged"), "Unchanged");
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(ScrubRules::from_json(r#"[{"pattern": "("}]"#).is_err());
+    }
+}
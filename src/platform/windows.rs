@@ -1,49 +1,109 @@
 use anyhow::Result;
-use log::error;
-use std::collections::BTreeMap;
+use log::{debug, error};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::os::windows::prelude::*;
+use std::rc::Rc;
 use std::time::Duration;
 use std::{ffi::OsString, path::Path};
+use windows::core::{w, Interface, HSTRING, PWSTR};
+use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
 use windows::Win32::Foundation::LPARAM;
 use windows::Win32::Foundation::{BOOL, RECT};
 use windows::Win32::UI::WindowsAndMessaging::{
     EnumWindows, GetWindowRect, GetWindowTextLengthW, GetWindowTextW, IsWindowVisible,
 };
 use windows::Win32::{
-    Foundation::{CloseHandle, FALSE, HINSTANCE, HWND},
+    Foundation::{CloseHandle, FALSE, FILETIME, HANDLE, HINSTANCE, HWND},
+    Media::Audio::{
+        eMultimedia, eRender, AudioSessionStateActive, IAudioSessionControl2,
+        IAudioSessionManager2, IMMDeviceEnumerator, MMDeviceEnumerator,
+    },
+    Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+        VS_FIXEDFILEINFO,
+    },
     System::{
+        Com::{CoCreateInstance, CLSCTX_ALL},
+        Diagnostics::ToolHelp::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+            TH32CS_SNAPPROCESS,
+        },
         ProcessStatus::GetModuleFileNameExW,
-        SystemInformation::GetTickCount,
-        Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
+        RemoteDesktop::{
+            ProcessIdToSessionId, WTSActive, WTSConnectState, WTSFreeMemory,
+            WTSGetActiveConsoleSessionId, WTSQuerySessionInformationW, WTS_CONNECTSTATE_CLASS,
+            WTS_CURRENT_SERVER_HANDLE, WTS_CURRENT_SESSION,
+        },
+        StationsAndDesktops::{
+            CloseDesktop, GetUserObjectInformationW, OpenInputDesktop, DESKTOP_CONTROL_FLAGS,
+            DESKTOP_READOBJECTS, UOI_NAME,
+        },
+        SystemInformation::GetTickCount64,
+        Threading::{
+            GetCurrentProcessId, GetProcessTimes, OpenProcess, QueryFullProcessImageNameW,
+            PROCESS_NAME_WIN32, PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+            PROCESS_VM_READ,
+        },
+        WindowsProgramming::GetUserNameW,
     },
     UI::{
         Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
-        WindowsAndMessaging::{GetWindowTextA, GetWindowTextLengthA, GetWindowThreadProcessId},
+        WindowsAndMessaging::{
+            GetForegroundWindow, GetSystemMetrics, GetWindowThreadProcessId, MessageBoxW,
+            MB_ICONWARNING, MB_OK, SM_REMOTESESSION,
+        },
     },
 };
 
-use crate::platform::WindowDetails;
+use crate::platform::com_thread::run_on_com_thread;
+use crate::platform::{foreground, power};
+use crate::platform::{window_key, PrivacySettings, ProcessInfo, WindowDetails};
 
 use super::Platform;
 
 pub struct WindowsHandle;
 
+/// Parent id and executable name by process id
+type ParentMap = HashMap<u32, (u32, String)>;
+
+fn parent_map(processes: Vec<ProcessInfo>) -> ParentMap {
+    processes
+        .into_iter()
+        .map(|process| (process.process_id, (process.parent_id, process.exe_name)))
+        .collect()
+}
+
+thread_local! {
+    /// `Some` while windows are being enumerated, so helper windows of one pass
+    /// share a process snapshot that is taken on first use
+    static PARENT_MAP_CACHE: RefCell<Option<Option<Rc<ParentMap>>>> = const { RefCell::new(None) };
+}
+
 impl Platform for WindowsHandle {
-    fn get_window_titles() -> BTreeMap<String, WindowDetails> {
+    fn get_window_titles(privacy: &PrivacySettings) -> BTreeMap<String, WindowDetails> {
         let state: Box<BTreeMap<String, WindowDetails>> = Box::new(BTreeMap::new());
         let state_ptr = Box::into_raw(state);
         let state;
+        PARENT_MAP_CACHE.set(Some(None));
         let result = unsafe { EnumWindows(Some(enumerate_windows), LPARAM(state_ptr as isize)) };
+        PARENT_MAP_CACHE.set(None);
         if result.is_err() {
             error!("Unable to get the window titles.");
         }
         state = unsafe { Box::from_raw(state_ptr) };
-        *state
+        let mut state = *state;
+        mark_audio_playing(&mut state);
+        let is_remote_session = unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0;
+        for details in state.values_mut() {
+            details.is_remote_session = is_remote_session;
+        }
+        privacy.apply(state)
     }
 
     fn get_last_input_info() -> Result<Duration, ()> {
         unsafe {
-            let now = GetTickCount();
+            let now = GetTickCount64();
             let mut last_input_info = LASTINPUTINFO {
                 cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
                 dwTime: 0,
@@ -53,18 +113,296 @@ impl Platform for WindowsHandle {
                 error!("Failed to retrieve the last input time.");
                 return Err(());
             }
-            let millis = now - last_input_info.dwTime;
+            // dwTime is a 32-bit tick count, so compare against the low bits of the
+            // 64-bit counter with wrapping arithmetic to survive the 49.7 day rollover.
+            let millis = (now as u32).wrapping_sub(last_input_info.dwTime);
             Ok(Duration::from_millis(millis as u64))
         }
     }
+
+    fn get_uptime() -> Duration {
+        Duration::from_millis(unsafe { GetTickCount64() })
+    }
+
+    fn is_session_locked() -> bool {
+        // While locked the input desktop is the secure Winlogon desktop, which a
+        // regular user can't open at all.
+        let desktop =
+            match unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), FALSE, DESKTOP_READOBJECTS) }
+            {
+                Ok(desktop) => desktop,
+                Err(_) => return true,
+            };
+        let mut name: [u16; 256] = [0; 256];
+        let mut needed: u32 = 0;
+        let result = unsafe {
+            GetUserObjectInformationW(
+                HANDLE(desktop.0),
+                UOI_NAME,
+                Some(name.as_mut_ptr() as *mut _),
+                (name.len() * std::mem::size_of::<u16>()) as u32,
+                Some(&mut needed),
+            )
+        };
+        let _ = unsafe { CloseDesktop(desktop) };
+        if result.is_err() {
+            return false;
+        }
+        let length = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        !String::from_utf16_lossy(&name[..length]).eq_ignore_ascii_case("Default")
+    }
+
+    fn is_session_active() -> bool {
+        // The session owning the physical console is always in use
+        let mut session_id: u32 = 0;
+        if unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) }.is_err() {
+            return true;
+        }
+        if session_id == unsafe { WTSGetActiveConsoleSessionId() } {
+            return true;
+        }
+
+        // Otherwise the session is either connected over Remote Desktop or was
+        // switched away from with fast user switching and is now disconnected
+        let mut buffer = PWSTR::null();
+        let mut bytes: u32 = 0;
+        let queried = unsafe {
+            WTSQuerySessionInformationW(
+                WTS_CURRENT_SERVER_HANDLE,
+                WTS_CURRENT_SESSION,
+                WTSConnectState,
+                &mut buffer,
+                &mut bytes,
+            )
+        };
+        if queried.is_err() || buffer.is_null() {
+            return true;
+        }
+        let state = unsafe { *(buffer.0 as *const WTS_CONNECTSTATE_CLASS) };
+        unsafe { WTSFreeMemory(buffer.0 as *mut _) };
+        state == WTSActive
+    }
+
+    fn is_display_off() -> bool {
+        power::is_display_off()
+    }
+
+    fn is_metered_connection() -> bool {
+        let cost = NetworkInformation::GetInternetConnectionProfile()
+            .and_then(|profile| profile.GetConnectionCost());
+        match cost {
+            Ok(cost) => {
+                let cost_type = cost.NetworkCostType().unwrap_or(NetworkCostType::Unknown);
+                cost_type == NetworkCostType::Fixed
+                    || cost_type == NetworkCostType::Variable
+                    || cost.Roaming().unwrap_or(false)
+                    || cost.OverDataLimit().unwrap_or(false)
+            }
+            // No internet profile means there is no connection to meter
+            Err(_) => false,
+        }
+    }
+
+    fn get_last_focus_change() -> Option<chrono::NaiveDateTime> {
+        foreground::last_foreground_change()
+    }
+
+    fn get_foreground_app() -> Option<String> {
+        let window = unsafe { GetForegroundWindow() };
+        if window.is_invalid() {
+            return None;
+        }
+        let path_name = get_process_name(window).ok()?;
+        let app_name = get_app_name_from_path(&path_name)?;
+        Some(
+            get_virtualized_app_name(&app_name, &path_name)
+                .map(str::to_string)
+                .unwrap_or(app_name),
+        )
+    }
+
+    fn get_running_processes() -> Vec<ProcessInfo> {
+        get_process_snapshot().unwrap_or_default()
+    }
+
+    fn get_user_name() -> Option<String> {
+        // UNLEN + 1
+        let mut buffer: [u16; 257] = [0; 257];
+        let mut length = buffer.len() as u32;
+        let result = unsafe { GetUserNameW(PWSTR(buffer.as_mut_ptr()), &mut length) };
+        if let Err(err) = result {
+            error!("Failed to retrieve the user name: {:?}", err);
+            return None;
+        }
+        // The returned length includes the terminating null
+        let length = (length as usize).saturating_sub(1);
+        String::from_utf16(&buffer[..length]).ok()
+    }
+
+    fn get_file_version(path: &str) -> Option<String> {
+        let path = HSTRING::from(path);
+        unsafe {
+            let size = GetFileVersionInfoSizeW(&path, None);
+            if size == 0 {
+                return None;
+            }
+            let mut data: Vec<u8> = vec![0; size as usize];
+            if GetFileVersionInfoW(&path, 0, size, data.as_mut_ptr() as *mut _).is_err() {
+                return None;
+            }
+            let mut info: *mut VS_FIXEDFILEINFO = std::ptr::null_mut();
+            let mut info_len: u32 = 0;
+            let found = VerQueryValueW(
+                data.as_ptr() as *const _,
+                w!("\\"),
+                &mut info as *mut _ as *mut *mut _,
+                &mut info_len,
+            );
+            if !found.as_bool() || info.is_null() {
+                return None;
+            }
+            let info = &*info;
+            Some(format!(
+                "{}.{}.{}.{}",
+                info.dwFileVersionMS >> 16,
+                info.dwFileVersionMS & 0xffff,
+                info.dwFileVersionLS >> 16,
+                info.dwFileVersionLS & 0xffff
+            ))
+        }
+    }
+
+    fn get_free_disk_space(path: &Path) -> Option<u64> {
+        // The file itself may not exist yet, its directory always does
+        let directory = path
+            .parent()
+            .filter(|parent| parent.exists())
+            .unwrap_or(path);
+        let mut free_bytes: u64 = 0;
+        let result = unsafe {
+            GetDiskFreeSpaceExW(
+                &HSTRING::from(directory.as_os_str()),
+                Some(&mut free_bytes),
+                None,
+                None,
+            )
+        };
+        if let Err(err) = result {
+            error!(
+                "Failed to get free disk space for {:?}: {:?}",
+                directory, err
+            );
+            return None;
+        }
+        Some(free_bytes)
+    }
+
+    fn show_alert(title: &str, message: &str) {
+        let (title, message) = (HSTRING::from(title), HSTRING::from(message));
+        // The message box blocks until dismissed, so keep it off the caller's thread.
+        // It neither takes the foreground nor blocks other windows, so it waits
+        // behind whatever the user is doing instead of interrupting it.
+        let spawned = std::thread::Builder::new()
+            .name("alert".to_string())
+            .spawn(move || unsafe {
+                MessageBoxW(None, &message, &title, MB_OK | MB_ICONWARNING);
+            });
+        if let Err(err) = spawned {
+            error!("Failed to spawn the alert thread: {:?}", err);
+        }
+    }
 }
 
 fn get_process_name(current_window: HWND) -> Result<String, ()> {
-    let length = unsafe { GetWindowTextLengthA(current_window) };
-    let mut title: Vec<u8> = vec![0; (length + 1) as usize];
-    let _ = unsafe { GetWindowTextA(current_window, &mut title) };
     let mut process_id: u32 = 0;
     unsafe { GetWindowThreadProcessId(current_window, Some(&mut process_id)) };
+    let path = get_process_path(process_id)?;
+    // Windows shown by helper processes belong to the app that started them
+    if get_app_name_from_path(&path).is_some_and(|name| is_helper_executable(&name)) {
+        let owner = get_parent_map()
+            .and_then(|parents| find_owning_process(&parents, process_id, started_before));
+        if let Some(path) = owner.and_then(|id| get_process_path(id).ok()) {
+            return Ok(path);
+        }
+    }
+    Ok(path)
+}
+
+/// Whether the executable only runs on behalf of another app, like embedded
+/// browsers and crash reporters
+fn is_helper_executable(exe_name: &str) -> bool {
+    HELPER_EXECUTABLES.contains(&exe_name.to_ascii_lowercase().as_str())
+}
+
+/// Whether `parent` started no later than `child`. The parent may have exited and
+/// its id been reused by an unrelated process, which then started after the child.
+/// Unknown start times can't rule that out.
+fn started_before(parent: u32, child: u32) -> bool {
+    get_process_creation_time(parent)
+        .zip(get_process_creation_time(child))
+        .is_some_and(|(parent, child)| parent <= child)
+}
+
+/// Nearest ancestor of a helper process that isn't a helper itself. Helpers
+/// started straight from the shell or a system service have no owning app, and
+/// the walk stops at any parent that `started_before` says can't be the real one.
+fn find_owning_process(
+    parents: &ParentMap,
+    process_id: u32,
+    started_before: impl Fn(u32, u32) -> bool,
+) -> Option<u32> {
+    let mut current = process_id;
+    for _ in 0..MAX_PARENT_DEPTH {
+        let (parent_id, _) = parents.get(&current)?;
+        let (_, parent_exe) = parents.get(parent_id).filter(|_| *parent_id != current)?;
+        if !started_before(*parent_id, current) {
+            return None;
+        }
+        if LAUNCHER_EXECUTABLES.contains(&parent_exe.to_ascii_lowercase().as_str()) {
+            return None;
+        }
+        if !is_helper_executable(parent_exe) {
+            return Some(*parent_id);
+        }
+        current = *parent_id;
+    }
+    None
+}
+
+/// The enumeration's cached parent map, or a fresh one when called outside an
+/// enumeration such as from the idle monitor's thread
+fn get_parent_map() -> Option<Rc<ParentMap>> {
+    let load = || Some(Rc::new(parent_map(get_process_snapshot().ok()?)));
+    PARENT_MAP_CACHE.with_borrow_mut(|cache| match cache {
+        None => load(),
+        Some(Some(parents)) => Some(parents.clone()),
+        Some(cached) => {
+            *cached = load();
+            cached.clone()
+        }
+    })
+}
+
+/// Creation time of a process in FILETIME ticks
+fn get_process_creation_time(process_id: u32) -> Option<u64> {
+    let h = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id) }.ok()?;
+    let mut creation = FILETIME::default();
+    let (mut exit, mut kernel, mut user) = Default::default();
+    let result = unsafe { GetProcessTimes(h, &mut creation, &mut exit, &mut kernel, &mut user) };
+    let _ = unsafe { CloseHandle(h) };
+    result.ok()?;
+    Some((u64::from(creation.dwHighDateTime) << 32) | u64::from(creation.dwLowDateTime))
+}
+
+fn get_process_path(process_id: u32) -> Result<String, ()> {
+    // Elevated and protected processes refuse PROCESS_VM_READ, so fall back to
+    // the limited query right and finally to the process snapshot exe name.
+    get_module_file_name(process_id)
+        .or_else(|_| get_full_process_image_name(process_id))
+        .or_else(|_| get_snapshot_exe_name(process_id))
+}
+
+fn get_module_file_name(process_id: u32) -> Result<String, ()> {
     let handle = unsafe {
         OpenProcess(
             PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
@@ -73,7 +411,7 @@ fn get_process_name(current_window: HWND) -> Result<String, ()> {
         )
     };
     let h = handle.map_err(|e| {
-        error!("Failed to open process: {:?}", e);
+        debug!("Failed to open process {} for reading: {:?}", process_id, e);
     })?;
     let mut buffer: [u16; 260] = [0; 260];
     let result = unsafe { GetModuleFileNameExW(h, HINSTANCE::default(), &mut buffer) };
@@ -82,7 +420,10 @@ fn get_process_name(current_window: HWND) -> Result<String, ()> {
     //     error!("Failed to close handle: {:?}", h);
     // }
     if result == 0 {
-        error!("Failed to retrieve the module file name.");
+        debug!(
+            "Failed to retrieve the module file name for {}.",
+            process_id
+        );
         return Err(());
     }
     let path = OsString::from_wide(&buffer[..result as usize])
@@ -91,6 +432,176 @@ fn get_process_name(current_window: HWND) -> Result<String, ()> {
     Ok(path)
 }
 
+fn get_full_process_image_name(process_id: u32) -> Result<String, ()> {
+    let h = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id) }.map_err(
+        |e| {
+            debug!(
+                "Failed to open process {} with limited rights: {:?}",
+                process_id, e
+            );
+        },
+    )?;
+    let mut buffer: [u16; 1024] = [0; 1024];
+    let mut length = buffer.len() as u32;
+    let result = unsafe {
+        QueryFullProcessImageNameW(
+            h,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut length,
+        )
+    };
+    let _ = unsafe { CloseHandle(h) };
+    if let Err(e) = result {
+        debug!("Failed to query the image name for {}: {:?}", process_id, e);
+        return Err(());
+    }
+    let path = OsString::from_wide(&buffer[..length as usize])
+        .to_string_lossy()
+        .into_owned();
+    Ok(path)
+}
+
+/// Last resort for processes that can't be opened at all: only the exe name is known
+fn get_snapshot_exe_name(process_id: u32) -> Result<String, ()> {
+    get_process_snapshot()?
+        .into_iter()
+        .find(|process| process.process_id == process_id)
+        .map(|process| process.exe_name)
+        .ok_or(())
+}
+
+/// Process id and executable name of every running process
+fn get_process_snapshot() -> Result<Vec<ProcessInfo>, ()> {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }.map_err(|e| {
+        error!("Failed to snapshot processes: {:?}", e);
+    })?;
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+    let mut processes = Vec::new();
+    let mut next = unsafe { Process32FirstW(snapshot, &mut entry) };
+    while next.is_ok() {
+        let length = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        processes.push(ProcessInfo {
+            process_id: entry.th32ProcessID,
+            parent_id: entry.th32ParentProcessID,
+            exe_name: OsString::from_wide(&entry.szExeFile[..length])
+                .to_string_lossy()
+                .into_owned(),
+        });
+        next = unsafe { Process32NextW(snapshot, &mut entry) };
+    }
+    let _ = unsafe { CloseHandle(snapshot) };
+    Ok(processes)
+}
+
+/// Flag windows whose executable currently has an active audio session.
+/// Matching on the path also covers browsers that play audio from a helper process.
+fn mark_audio_playing(state: &mut BTreeMap<String, WindowDetails>) {
+    let playing_paths: HashSet<String> = get_active_audio_process_ids()
+        .into_iter()
+        .filter_map(|process_id| get_process_path(process_id).ok())
+        .collect();
+    if playing_paths.is_empty() {
+        return;
+    }
+    for details in state.values_mut() {
+        details.is_playing_audio = details
+            .app_path
+            .as_ref()
+            .is_some_and(|path| playing_paths.contains(path));
+    }
+}
+
+fn get_active_audio_process_ids() -> Vec<u32> {
+    run_on_com_thread(|| unsafe { query_audio_sessions() })
+        .unwrap_or_else(|| Ok(Vec::new()))
+        .unwrap_or_else(|err| {
+            debug!("Failed to query audio sessions: {:?}", err);
+            Vec::new()
+        })
+}
+
+unsafe fn query_audio_sessions() -> windows::core::Result<Vec<u32>> {
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+    let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+    let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+    let sessions = manager.GetSessionEnumerator()?;
+
+    let mut process_ids = Vec::new();
+    for index in 0..sessions.GetCount()? {
+        let control = sessions.GetSession(index)?;
+        if control.GetState()? != AudioSessionStateActive {
+            continue;
+        }
+        let control: IAudioSessionControl2 = control.cast()?;
+        match control.GetProcessId() {
+            Ok(process_id) if process_id != 0 => process_ids.push(process_id),
+            _ => {}
+        }
+    }
+    Ok(process_ids)
+}
+
+/// Host processes that draw windows for WSL distributions
+const WSL_HOST_EXECUTABLES: &[&str] = &["wslhost.exe", "wsl.exe", "vmmem", "vmmemwsl"];
+
+/// Host processes that show virtual machine consoles
+const VM_HOST_EXECUTABLES: &[&str] = &[
+    "vmconnect.exe",
+    "virtualboxvm.exe",
+    "vmware.exe",
+    "vmplayer.exe",
+    "vmware-vmx.exe",
+    "qemu-system-x86_64.exe",
+    "qemu-system-x86_64w.exe",
+];
+
+/// Executables that show windows for the app that started them
+const HELPER_EXECUTABLES: &[&str] = &[
+    "msedgewebview2.exe",
+    "cefsharp.browsersubprocess.exe",
+    "qtwebengineprocess.exe",
+    "crashpad_handler.exe",
+    "webviewhost.exe",
+];
+
+/// Parents that start apps for the user, so a helper under them stands alone
+const LAUNCHER_EXECUTABLES: &[&str] = &[
+    "explorer.exe",
+    "svchost.exe",
+    "services.exe",
+    "wininit.exe",
+    "winlogon.exe",
+    "sihost.exe",
+];
+
+/// Process ancestors followed before giving up on finding the owning app
+const MAX_PARENT_DEPTH: usize = 8;
+
+/// Pseudo-app name for windows whose real work happens inside WSL or a VM.
+/// WSLg windows belong to an msrdc.exe shipped inside the WSL package, unlike
+/// the regular Remote Desktop client in System32.
+fn get_virtualized_app_name(app_name: &str, path: &str) -> Option<&'static str> {
+    let app_name = app_name.to_ascii_lowercase();
+    let path = path.to_ascii_lowercase();
+    let is_wslg = app_name == "msrdc.exe"
+        && (path.contains("windowssubsystemforlinux") || path.contains("\\wsl\\"));
+    if is_wslg || WSL_HOST_EXECUTABLES.contains(&app_name.as_str()) {
+        Some("WSL")
+    } else if VM_HOST_EXECUTABLES.contains(&app_name.as_str()) {
+        Some("VM")
+    } else {
+        None
+    }
+}
+
 fn get_app_name_from_path(path: &str) -> Option<String> {
     Path::new(path)
         .file_name()
@@ -127,14 +638,20 @@ unsafe extern "system" fn enumerate_windows(window: HWND, state: LPARAM) -> BOOL
             });
             let app_name = get_app_name_from_path(&path_name)
                 .unwrap_or_else(|| "Invalid app name".to_string());
+            // Per-VM titles are kept so the pseudo-app still separates machines
+            let app_name = get_virtualized_app_name(&app_name, &path_name)
+                .map(str::to_string)
+                .unwrap_or(app_name);
             if title != "Windows Input Experience" && title != "Program Manager" {
                 (*state).insert(
-                    title.clone(),
+                    window_key(Some(&app_name), &title),
                     WindowDetails {
                         window_title: title,
                         app_name: Some(app_name),
                         app_path: Some(path_name),
-                        is_active: false,
+                        is_active: window == GetForegroundWindow(),
+                        is_playing_audio: false,
+                        is_remote_session: false,
                     },
                 );
             }
@@ -142,3 +659,57 @@ unsafe extern "system" fn enumerate_windows(window: HWND, state: LPARAM) -> BOOL
     }
     BOOL::from(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// explorer.exe started Code.exe and updatehelper.exe, which each run
+    /// embedded browsers, and a crash reporter runs straight from the shell.
+    /// One embedded browser outlived the process that started it.
+    fn process_tree() -> ParentMap {
+        let processes = [
+            (1, 0, "explorer.exe"),
+            (10, 1, "Code.exe"),
+            (11, 10, "msedgewebview2.exe"),
+            (12, 11, "msedgewebview2.exe"),
+            (20, 1, "updatehelper.exe"),
+            (21, 20, "msedgewebview2.exe"),
+            (30, 1, "crashpad_handler.exe"),
+            (40, 99, "msedgewebview2.exe"),
+        ];
+        parent_map(
+            processes
+                .into_iter()
+                .map(|(process_id, parent_id, exe_name)| ProcessInfo {
+                    process_id,
+                    parent_id,
+                    exe_name: exe_name.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn helpers_belong_to_the_nearest_non_helper_ancestor() {
+        let parents = process_tree();
+        let always = |_, _| true;
+        assert_eq!(find_owning_process(&parents, 11, always), Some(10));
+        assert_eq!(find_owning_process(&parents, 12, always), Some(10));
+        // Only listed executables are helpers, whatever their name says
+        assert_eq!(find_owning_process(&parents, 21, always), Some(20));
+        assert!(!is_helper_executable("updatehelper.exe"));
+    }
+
+    #[test]
+    fn helpers_without_an_owning_app_stand_alone() {
+        let parents = process_tree();
+        let always = |_, _| true;
+        assert_eq!(find_owning_process(&parents, 30, always), None);
+        // The parent has exited
+        assert_eq!(find_owning_process(&parents, 40, always), None);
+        // The parent's id was reused by a process that started later
+        let reused = |parent, _| parent != 10;
+        assert_eq!(find_owning_process(&parents, 12, reused), None);
+    }
+}
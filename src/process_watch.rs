@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Local;
+use log::{error, info};
+use rusqlite::Connection;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::db::models::ProcessLifetime;
+use crate::db::processes::upsert_process_lifetime;
+use crate::disk_guard::DiskGuard;
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Parse the comma separated `WATCHED_PROCESSES` setting into executable names
+pub fn parse_watchlist(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Poll running processes and record how long each watched executable runs,
+/// including launchers and updaters that never show a qualifying window
+pub async fn track_watched_processes(
+    conn: Arc<Mutex<Connection>>,
+    session_id: String,
+    watchlist: Vec<String>,
+    disk_guard: DiskGuard,
+) {
+    if watchlist.is_empty() {
+        return;
+    }
+    info!("Watching processes: {:?}", watchlist);
+
+    let mut running: HashMap<u32, ProcessLifetime> = HashMap::new();
+    let mut interval = tokio::time::interval(PROCESS_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        if disk_guard.is_low_on_space() {
+            continue;
+        }
+
+        let now = Local::now().naive_utc();
+        let processes = WindowsHandle::get_running_processes();
+        let exe_names: HashMap<u32, &str> = processes
+            .iter()
+            .map(|process| (process.process_id, process.exe_name.as_str()))
+            .collect();
+        // Child processes of the same executable are rolled up into the top-most one
+        let watched: HashMap<u32, String> = processes
+            .iter()
+            .filter(|process| {
+                watchlist
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&process.exe_name))
+            })
+            .filter(|process| {
+                process.parent_id == process.process_id
+                    || exe_names
+                        .get(&process.parent_id)
+                        .is_none_or(|parent| !parent.eq_ignore_ascii_case(&process.exe_name))
+            })
+            .map(|process| (process.process_id, process.exe_name.clone()))
+            .collect();
+
+        // Exited processes keep the end time of the last poll they were seen in
+        running.retain(|process_id, lifetime| watched.get(process_id) == Some(&lifetime.exe_name));
+        for (process_id, exe_name) in watched {
+            let lifetime = running
+                .entry(process_id)
+                .or_insert_with(|| ProcessLifetime {
+                    id: Uuid::new_v4().to_string(),
+                    session_id: session_id.clone(),
+                    process_id,
+                    exe_name,
+                    start_time: now,
+                    end_time: now,
+                });
+            lifetime.end_time = now;
+        }
+
+        let conn = conn.lock().await;
+        for lifetime in running.values() {
+            if let Err(err) = upsert_process_lifetime(&conn, lifetime) {
+                error!(
+                    "Failed to record process {} ({}): {}",
+                    lifetime.exe_name, lifetime.process_id, err
+                );
+            }
+        }
+    }
+}
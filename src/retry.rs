@@ -0,0 +1,105 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use log::warn;
+
+/// Exponential backoff with jitter shared by every retry loop
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    /// Attempts before giving up, counting the first one
+    pub max_attempts: u32,
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `failures` consecutive failures, capped at
+    /// `max_delay` with up to half of it randomised so retries don't line up
+    pub fn delay(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        let delay = self
+            .initial_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = delay.as_millis() as u64 / 2;
+        if jitter == 0 {
+            return delay;
+        }
+        delay - Duration::from_millis(random_u64() % jitter)
+    }
+
+    /// Whether `failures` consecutive failures have used up every attempt
+    pub fn is_exhausted(&self, failures: u32) -> bool {
+        failures >= self.max_attempts
+    }
+
+    /// Run `operation` until it succeeds or the attempts run out, sleeping between tries
+    pub async fn retry<T, E, F, Fut>(&self, name: &str, mut operation: F) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut failures = 0;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    failures += 1;
+                    if self.is_exhausted(failures) {
+                        return Err(err);
+                    }
+                    let delay = self.delay(failures);
+                    warn!(
+                        "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                        name, failures, self.max_attempts, delay, err
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Cheap random number from the std hasher seed, good enough for jitter
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(5),
+        max_attempts: 4,
+    };
+
+    #[test]
+    fn delay_doubles_with_jitter_up_to_the_cap() {
+        for _ in 0..100 {
+            for (failures, full_delay) in
+                [(1, 100), (2, 200), (3, 400), (10, 5000), (u32::MAX, 5000)]
+            {
+                let delay = POLICY.delay(failures).as_millis() as u64;
+                assert!(
+                    full_delay / 2 < delay && delay <= full_delay,
+                    "{} failures gave {}ms",
+                    failures,
+                    delay
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn attempts_include_the_first_one() {
+        assert!(!POLICY.is_exhausted(3));
+        assert!(POLICY.is_exhausted(4));
+    }
+}
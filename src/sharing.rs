@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use log::{debug, error, info};
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::db::reports::{activity_totals, total_screen_time, ActivityTotal, DayBoundary};
+use crate::disk_guard::DiskGuard;
+use crate::platform::windows::WindowsHandle;
+use crate::platform::Platform;
+
+const SHARED_STATS_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Aggregate stats shared with a partner. Only totals and literal activity
+/// names are included, never app names or window titles.
+#[derive(Debug, Serialize)]
+struct SharedStats {
+    user_name: String,
+    generated_at: String,
+    date: NaiveDate,
+    total_seconds: u64,
+    activities: Vec<ActivityTotal>,
+}
+
+/// Periodically export today's aggregate stats to a file a partner can read,
+/// such as one in a synced or network folder. Only activities named in
+/// `shared_activities` are listed.
+pub async fn export_shared_stats(
+    conn: Arc<Mutex<Connection>>,
+    path: PathBuf,
+    boundary: DayBoundary,
+    shared_activities: HashSet<String>,
+    disk_guard: DiskGuard,
+) {
+    info!("Sharing aggregate stats at {:?}", path);
+    let user_name = WindowsHandle::get_user_name().unwrap_or_else(|| "Unknown User".to_string());
+    let mut interval = tokio::time::interval(SHARED_STATS_INTERVAL);
+    loop {
+        interval.tick().await;
+        if disk_guard.is_low_on_space() {
+            continue;
+        }
+
+        let totals = {
+            let conn = conn.lock().await;
+            let since = boundary.start_of_today();
+            let now = Local::now().naive_utc();
+            activity_totals(&conn, since, now)
+                .and_then(|activities| Ok((total_screen_time(&conn, since, now)?, activities)))
+        };
+        let (total_seconds, activities) = match totals {
+            Ok(totals) => totals,
+            Err(err) => {
+                error!("Failed to query shared stats: {}", err);
+                continue;
+            }
+        };
+
+        let stats = SharedStats {
+            user_name: user_name.clone(),
+            generated_at: Local::now().to_rfc3339(),
+            date: boundary.today(),
+            total_seconds,
+            activities: shared_totals(activities, &shared_activities),
+        };
+
+        // Write to a temporary file first so readers never see a partial document
+        let tmp_path = path.with_extension("json.tmp");
+        let result = serde_json::to_vec_pretty(&stats)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| std::fs::write(&tmp_path, contents))
+            .and_then(|_| std::fs::rename(&tmp_path, &path));
+        match result {
+            Ok(_) => debug!("Shared stats written to {:?}", path),
+            Err(err) => error!("Failed to write shared stats {:?}: {:?}", path, err),
+        }
+    }
+}
+
+/// Drop activities whose names may have been built from window titles
+fn shared_totals(
+    activities: Vec<ActivityTotal>,
+    shared_activities: &HashSet<String>,
+) -> Vec<ActivityTotal> {
+    activities
+        .into_iter()
+        .filter(|activity| shared_activities.contains(&activity.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::ActivityRules;
+
+    #[test]
+    fn title_captures_are_not_shared() {
+        let rules = ActivityRules::from_json(
+            r#"[
+                {"activity": "Coding $project", "pattern": "^(?P<project>\\w+) - Visual Studio Code$"},
+                {"activity": "Email", "app_name": "outlook.exe"}
+            ]"#,
+        )
+        .unwrap();
+        let activity = rules
+            .classify("code.exe", "secret_project - Visual Studio Code")
+            .unwrap();
+        assert_eq!(activity, "Coding secret_project");
+
+        let totals = [activity.as_str(), "Email", "Games"]
+            .map(|name| ActivityTotal {
+                name: name.to_string(),
+                seconds: 60,
+            })
+            .to_vec();
+        let shared: Vec<String> = shared_totals(totals, &rules.literal_activities())
+            .into_iter()
+            .map(|activity| activity.name)
+            .collect();
+        assert_eq!(shared, ["Email", "Games"]);
+    }
+}
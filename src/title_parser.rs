@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use anyhow::Result;
+use log::{error, info};
+use regex::Regex;
+use serde::Deserialize;
+
+/// A rule as written in the title rules file
+#[derive(Debug, Deserialize)]
+struct TitleRuleConfig {
+    app_name: String,
+    pattern: String,
+}
+
+struct TitleRule {
+    app_name: String,
+    pattern: Regex,
+}
+
+/// Structured fields extracted from a window title
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedTitle {
+    pub project: Option<String>,
+    pub file: Option<String>,
+    /// Git repository shown by an editor or terminal
+    pub repository: Option<String>,
+    pub branch: Option<String>,
+}
+
+/// Opt-in per-app title parsing. Each rule is a regex whose `project`, `file`,
+/// `repository` and `branch` named capture groups are copied into the
+/// matching usage row.
+#[derive(Default)]
+pub struct TitleParser {
+    rules: Vec<TitleRule>,
+}
+
+impl TitleParser {
+    /// Load rules from a JSON file, a missing file means parsing is disabled
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::read_rules(path) {
+            Ok(parser) => {
+                info!("Loaded {} title rules from {:?}", parser.rules.len(), path);
+                parser
+            }
+            Err(err) => {
+                error!("Failed to load title rules from {:?}: {:?}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    fn read_rules(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse rules in the format of the rules file
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let configs: Vec<TitleRuleConfig> = serde_json::from_str(contents)?;
+        let rules = configs
+            .into_iter()
+            .map(|config| {
+                Ok(TitleRule {
+                    app_name: config.app_name,
+                    pattern: Regex::new(&config.pattern)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Apply the first rule for `app_name` that matches the title
+    pub fn parse(&self, app_name: &str, title: &str) -> ParsedTitle {
+        self.rules
+            .iter()
+            .filter(|rule| rule.app_name.eq_ignore_ascii_case(app_name))
+            .find_map(|rule| rule.pattern.captures(title))
+            .map(|captures| {
+                let group = |name| captures.name(name).map(|m| m.as_str().to_string());
+                ParsedTitle {
+                    project: group("project"),
+                    file: group("file"),
+                    repository: group("repository"),
+                    branch: group("branch"),
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> TitleParser {
+        TitleParser::from_json(
+            r#"[
+                {"app_name": "Code.exe", "pattern": "^(?P<file>[^ ]+) - (?P<repository>[^ ]+) \\((?P<branch>[^)]+)\\) - Visual Studio Code$"},
+                {"app_name": "code.exe", "pattern": "^(?P<file>[^ ]+) - (?P<project>[^ ]+) - Visual Studio Code$"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn first_matching_rule_for_the_app_wins() {
+        let parser = parser();
+        assert_eq!(
+            parser.parse(
+                "code.exe",
+                "main.rs - tracker (fix-idle) - Visual Studio Code"
+            ),
+            ParsedTitle {
+                project: None,
+                file: Some("main.rs".to_string()),
+                repository: Some("tracker".to_string()),
+                branch: Some("fix-idle".to_string()),
+            }
+        );
+        assert_eq!(
+            parser.parse("CODE.EXE", "notes.md - docs - Visual Studio Code"),
+            ParsedTitle {
+                project: Some("docs".to_string()),
+                file: Some("notes.md".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn other_apps_and_titles_parse_to_nothing() {
+        let parser = parser();
+        let title = "main.rs - tracker - Visual Studio Code";
+        assert_eq!(parser.parse("notepad.exe", title), ParsedTitle::default());
+        assert_eq!(parser.parse("code.exe", "Welcome"), ParsedTitle::default());
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Local;
+use log::{debug, error};
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::db::reports::{app_totals_since, total_screen_time, AppTotal, DayBoundary};
+use crate::disk_guard::DiskGuard;
+
+const WIDGET_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Contents of the widget file read by Rainmeter or Windows widgets
+#[derive(Debug, Serialize)]
+struct WidgetPayload {
+    generated_at: String,
+    today_total_seconds: u64,
+    week_total_seconds: u64,
+    top_app: Option<AppTotal>,
+}
+
+/// Periodically refresh a small JSON file with today's screen time
+pub async fn refresh_widget_file(
+    conn: Arc<Mutex<Connection>>,
+    path: PathBuf,
+    unfocused_weight: f64,
+    boundary: DayBoundary,
+    disk_guard: DiskGuard,
+) {
+    let mut interval = tokio::time::interval(WIDGET_REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if disk_guard.is_low_on_space() {
+            continue;
+        }
+
+        let totals = {
+            let conn = conn.lock().await;
+            let since = boundary.start_of_today();
+            let now = Local::now().naive_utc();
+            app_totals_since(&conn, since, unfocused_weight).and_then(|apps| {
+                Ok((
+                    total_screen_time(&conn, since, now)?,
+                    total_screen_time(&conn, boundary.start_of_week(), now)?,
+                    apps,
+                ))
+            })
+        };
+        let (today_total_seconds, week_total_seconds, totals) = match totals {
+            Ok(totals) => totals,
+            Err(err) => {
+                error!("Failed to query widget totals: {}", err);
+                continue;
+            }
+        };
+
+        let payload = WidgetPayload {
+            generated_at: Local::now().to_rfc3339(),
+            today_total_seconds,
+            week_total_seconds,
+            top_app: totals.into_iter().next(),
+        };
+
+        // Write to a temporary file first so readers never see a partial document
+        let tmp_path = path.with_extension("json.tmp");
+        let result = serde_json::to_vec_pretty(&payload)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| std::fs::write(&tmp_path, contents))
+            .and_then(|_| std::fs::rename(&tmp_path, &path));
+        match result {
+            Ok(_) => debug!("Widget file refreshed at {:?}", path),
+            Err(err) => error!("Failed to write widget file {:?}: {:?}", path, err),
+        }
+    }
+}